@@ -0,0 +1,113 @@
+# autojump-rs integration for bash, generated by `autojump init bash`.
+# Add `eval "$(autojump init bash)"` to ~/.bashrc.
+
+export AUTOJUMP_SOURCED=1
+
+
+# set error file location
+if [[ -z "${AUTOJUMP_ERROR_PATH}" ]]; then
+    if [[ "$(uname)" == "Darwin" ]]; then
+        export AUTOJUMP_ERROR_PATH=~/Library/autojump/errors.log
+    elif [[ -n "${XDG_DATA_HOME}" ]]; then
+        export AUTOJUMP_ERROR_PATH="${XDG_DATA_HOME}/autojump/errors.log"
+    else
+        export AUTOJUMP_ERROR_PATH=~/.local/share/autojump/errors.log
+    fi
+fi
+
+if [[ ! -d "$(dirname "${AUTOJUMP_ERROR_PATH}")" ]]; then
+    mkdir -p "$(dirname "${AUTOJUMP_ERROR_PATH}")"
+fi
+
+
+# record the current directory
+__autojump_add() {
+    (autojump --add "${PWD}" >/dev/null 2>>"${AUTOJUMP_ERROR_PATH}" &) >/dev/null 2>&1
+}
+
+{{#prompt}}
+__autojump_hook() {
+    __autojump_add
+}
+{{/prompt}}
+{{#cd}}
+__autojump_hook() {
+    if [[ "${__autojump_oldpwd}" != "${PWD}" ]]; then
+        __autojump_oldpwd="${PWD}"
+        __autojump_add
+    fi
+}
+{{/cd}}
+
+case ${PROMPT_COMMAND} in
+    *__autojump_hook*)
+        ;;
+    *)
+        PROMPT_COMMAND="${PROMPT_COMMAND:+${PROMPT_COMMAND%;}; }__autojump_hook"
+        ;;
+esac
+
+
+# enable tab completion
+__autojump_complete() {
+    local IFS=$'\n'
+    COMPREPLY=($(autojump --complete "${COMP_WORDS[COMP_CWORD]}"))
+}
+complete -F __autojump_complete {{cmd}}
+
+
+# jump to the best match
+{{cmd}}() {
+    if [[ ${1} == -* ]] && [[ ${1} != "--" ]]; then
+        autojump "$@"
+        return
+    fi
+
+    local output
+    output="$(autojump "$@")"
+    if [[ -d "${output}" ]]; then
+        if [[ -t 1 ]]; then  # if stdout is a terminal, use colors
+            printf '\033[31m%s\033[0m\n' "${output}"
+        else
+            printf '%s\n' "${output}"
+        fi
+        cd "${output}"
+    else
+        echo "autojump: directory '$*' not found" >&2
+        echo "Try \`autojump --help\` for more information." >&2
+        false
+    fi
+}
+
+
+# jump to the best match below the current directory
+{{cmd}}c() {
+    if [[ ${1} == -* ]] && [[ ${1} != "--" ]]; then
+        autojump "$@"
+    elif [[ ${#} -eq 0 ]]; then
+        # an empty needle keeps {{cmd}} from taking the flag for a command
+        {{cmd}} "" --children
+    else
+        {{cmd}} "$@" --children
+    fi
+}
+
+
+# open the best match in the file manager
+{{cmd}}o() {
+    if [[ ${1} == -* ]] && [[ ${1} != "--" ]]; then
+        autojump "$@"
+    else
+        autojump "$@" --open
+    fi
+}
+
+
+# open the best match below the current directory in the file manager
+{{cmd}}co() {
+    if [[ ${1} == -* ]] && [[ ${1} != "--" ]]; then
+        autojump "$@"
+    else
+        autojump "$@" --children --open
+    fi
+}
@@ -0,0 +1,95 @@
+# autojump-rs integration for fish, generated by `autojump init fish`.
+# Add `autojump init fish | source` to ~/.config/fish/config.fish.
+
+set -gx AUTOJUMP_SOURCED 1
+
+
+# set error file location
+if not set -q AUTOJUMP_ERROR_PATH
+    if test (uname) = "Darwin"
+        set -gx AUTOJUMP_ERROR_PATH ~/Library/autojump/errors.log
+    else if test -n "$XDG_DATA_HOME"
+        set -gx AUTOJUMP_ERROR_PATH $XDG_DATA_HOME/autojump/errors.log
+    else
+        set -gx AUTOJUMP_ERROR_PATH ~/.local/share/autojump/errors.log
+    end
+end
+
+if test ! -d (dirname $AUTOJUMP_ERROR_PATH)
+    mkdir -p (dirname $AUTOJUMP_ERROR_PATH)
+end
+
+
+# record the current directory
+{{#prompt}}
+function __autojump_add --on-event fish_prompt
+    autojump --add $PWD >/dev/null 2>>$AUTOJUMP_ERROR_PATH &
+end
+{{/prompt}}
+{{#cd}}
+function __autojump_add --on-variable PWD
+    status --is-command-substitution; and return
+    autojump --add $PWD >/dev/null 2>>$AUTOJUMP_ERROR_PATH &
+end
+{{/cd}}
+
+
+# enable tab completion
+complete -x -c {{cmd}} -a '(autojump --complete (commandline -t))'
+
+
+# jump to the best match
+function {{cmd}}
+    switch "$argv"
+        case '-*'
+            autojump $argv
+        case '*'
+            set -l output (autojump $argv)
+            if test -d "$output"
+                set_color red
+                echo $output
+                set_color normal
+                cd $output
+            else
+                echo "autojump: directory '$argv' not found" >&2
+                echo "Try `autojump --help` for more information." >&2
+                return 1
+            end
+    end
+end
+
+
+# jump to the best match below the current directory
+function {{cmd}}c
+    switch "$argv"
+        case '-*'
+            autojump $argv
+        case ''
+            # an empty needle keeps {{cmd}} from taking the flag for a command
+            {{cmd}} "" --children
+        case '*'
+            {{cmd}} $argv --children
+    end
+end
+
+
+# open the best match in the file manager
+function {{cmd}}o
+    switch "$argv"
+        case '-*'
+            autojump $argv
+        case '*'
+            autojump $argv --open
+    end
+end
+
+
+# open the best match below the current directory in the file manager
+function {{cmd}}co
+    switch "$argv"
+        case '-*'
+            autojump $argv
+        case '*'
+            autojump $argv --children --open
+    end
+end
@@ -0,0 +1,150 @@
+# autojump-rs integration for PowerShell, generated by
+# `autojump init powershell`. Add
+# `Invoke-Expression (& { (autojump init powershell | Out-String) })`
+# to $PROFILE.
+
+$env:AUTOJUMP_SOURCED = "1"
+
+
+# set error file location
+if (-not $env:AUTOJUMP_ERROR_PATH) {
+    $env:AUTOJUMP_ERROR_PATH = if ($IsMacOS) {
+        Join-Path $HOME 'Library/autojump/errors.log'
+    } elseif ($IsLinux -and $env:XDG_DATA_HOME) {
+        Join-Path $env:XDG_DATA_HOME 'autojump/errors.log'
+    } elseif ($IsLinux) {
+        Join-Path $HOME '.local/share/autojump/errors.log'
+    } else {
+        Join-Path $env:APPDATA 'autojump\errors.log'
+    }
+}
+$null = New-Item -ItemType Directory -Force (Split-Path $env:AUTOJUMP_ERROR_PATH)
+
+
+# record the current directory, in the background so that a busy data file
+# doesn't hold up the prompt; thread jobs are much cheaper where available
+$global:__autojump_start_job = if (Get-Command Start-ThreadJob -ErrorAction Ignore) {
+    'Start-ThreadJob'
+} else {
+    'Start-Job'
+}
+
+function global:__autojump_add {
+    if ($PWD.Provider.Name -eq 'FileSystem') {
+        # clean up after the previous calls
+        Get-Job -Name __autojump_add -ErrorAction Ignore |
+            Where-Object State -ne Running |
+            Remove-Job
+        $null = & $global:__autojump_start_job -Name __autojump_add -ScriptBlock {
+            param($dir, $errors)
+            $null = & autojump --add $dir 2>> $errors
+        } -ArgumentList $PWD.ProviderPath, $env:AUTOJUMP_ERROR_PATH
+    }
+}
+
+{{#prompt}}
+function global:__autojump_hook {
+    __autojump_add
+}
+{{/prompt}}
+{{#cd}}
+function global:__autojump_hook {
+    if ($global:__autojump_oldpwd -ne $PWD.Path) {
+        $global:__autojump_oldpwd = $PWD.Path
+        __autojump_add
+    }
+}
+{{/cd}}
+
+if (-not $global:__autojump_prompt) {
+    $global:__autojump_prompt = $function:prompt
+    function global:prompt {
+        # keep the status of the last command for the original prompt
+        $status = $global:LASTEXITCODE
+        __autojump_hook
+        $global:LASTEXITCODE = $status
+        & $global:__autojump_prompt
+    }
+}
+
+
+# enable tab completion, always as numbered entries like `foo__2__/path`
+# so that they stay prefixed by what was typed
+Register-ArgumentCompleter -Native -CommandName {{cmd}}, {{cmd}}c, {{cmd}}o, {{cmd}}co -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $pattern = '{{tab_entry_pattern}}'
+    & autojump --complete "$wordToComplete" | Where-Object { $_ } | ForEach-Object {
+        $value = if ($_ -match $pattern) {
+            $_
+        } elseif ($wordToComplete -match $pattern) {
+            $wordToComplete
+        } elseif ($wordToComplete.Contains('__')) {
+            "${wordToComplete}__$_"
+        } else {
+            "${wordToComplete}__1__$_"
+        }
+        $null = $value -match $pattern
+        $path = $Matches[3]
+        $text = $value
+        if ($value -match '[\s''"`$;&|(){}]') {
+            $text = "'" + ($value -replace "'", "''") + "'"
+        }
+        [System.Management.Automation.CompletionResult]::new($text, $path, 'ParameterValue', $path)
+    }
+}
+
+
+# whether the arguments are a command for autojump itself
+function global:__autojump_is_command($arguments) {
+    $arguments.Count -gt 0 -and "$($arguments[0])".StartsWith('-') -and $arguments[0] -ne '--'
+}
+
+function global:__autojump_cd([string[]]$arguments) {
+    $output = & autojump @arguments
+    if ($LASTEXITCODE -eq 0 -and $output -and (Test-Path -LiteralPath $output -PathType Container)) {
+        Write-Host $output -ForegroundColor Red
+        Set-Location -LiteralPath $output
+    } else {
+        Write-Error "autojump: directory '$arguments' not found"
+    }
+}
+
+
+# jump to the best match
+function global:{{cmd}} {
+    if (__autojump_is_command $args) {
+        & autojump @args
+    } else {
+        __autojump_cd $args
+    }
+}
+
+
+# jump to the best match below the current directory
+function global:{{cmd}}c {
+    if (__autojump_is_command $args) {
+        & autojump @args
+    } else {
+        __autojump_cd ($args + '--children')
+    }
+}
+
+
+# open the best match in the file manager
+function global:{{cmd}}o {
+    if (__autojump_is_command $args) {
+        & autojump @args
+    } else {
+        & autojump @args --open
+    }
+}
+
+
+# open the best match below the current directory in the file manager
+function global:{{cmd}}co {
+    if (__autojump_is_command $args) {
+        & autojump @args
+    } else {
+        & autojump @args --children --open
+    }
+}
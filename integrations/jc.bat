@@ -2,7 +2,11 @@
 
 echo %*|>nul findstr /rx \-.*
 if ERRORLEVEL 1 (
-  "%~dp0\j.bat" "%cd%" %*
+  if "%~1"=="" (
+    "%~dp0\j.bat" "" --children
+  ) else (
+    "%~dp0\j.bat" %* --children
+  )
 ) else (
   "%~dp0\autojump" %*
 )
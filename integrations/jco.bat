@@ -2,7 +2,11 @@
 
 echo %*|>nul findstr /rx \-.*
 if ERRORLEVEL 1 (
-  "%~dp0\jc.bat" "%cd%" %*
+  if "%~1"=="" (
+    "%~dp0\jo.bat" "" --children
+  ) else (
+    "%~dp0\jo.bat" %* --children
+  )
 ) else (
   "%~dp0\autojump" %*
 )
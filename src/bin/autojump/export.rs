@@ -0,0 +1,162 @@
+use std::io;
+use std::io::Write;
+use std::str;
+
+use serde_derive::Serialize;
+
+use autojump::data;
+use autojump::data::Entry;
+use autojump::{Config, Result};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    /// A single array of records.
+    Json,
+    /// One record per line.
+    JsonLines,
+    Csv,
+}
+
+impl str::FromStr for ExportFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<ExportFormat, ()> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "jsonl" => Ok(ExportFormat::JsonLines),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The exported form of an entry. Fields are only ever added to the end,
+/// so that consumers of the CSV columns keep working.
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Escaped like in the text data file if not valid Unicode.
+    path: &'a str,
+    weight: f64,
+    last_access: Option<u64>,
+    visits: u64,
+}
+
+fn write_entries(w: &mut dyn Write, format: ExportFormat, entries: &[Entry]) -> io::Result<()> {
+    let paths: Vec<_> = entries.iter().map(|e| data::encode_path(&e.path)).collect();
+    let records = entries.iter().zip(paths.iter()).map(|(e, p)| Record {
+        path: p,
+        weight: e.weight,
+        last_access: e.last_access,
+        visits: e.visits,
+    });
+
+    match format {
+        ExportFormat::Json => {
+            let records: Vec<_> = records.collect();
+            serde_json::to_writer_pretty(&mut *w, &records)?;
+            writeln!(w)?;
+        }
+        ExportFormat::JsonLines => {
+            for record in records {
+                serde_json::to_writer(&mut *w, &record)?;
+                writeln!(w)?;
+            }
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut *w);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn export(config: &Config, format: ExportFormat) -> Result<()> {
+    data::try_compact(config)?;
+
+    let entries = {
+        let mut tmp = data::load(config)?;
+        // heaviest first, like the order of preference for jumping
+        tmp.sort_by(|a, b| b.cmp(a));
+        tmp
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    write_entries(&mut stdout, format, &entries)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export_to_string(format: ExportFormat) -> String {
+        let mut touched = Entry::new("/bar", 20.0);
+        touched.touch(1600000000);
+        let entries = vec![touched, Entry::new("/foo,baz", 10.5)];
+
+        let mut buf = vec![];
+        write_entries(&mut buf, format, &entries).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_export_json() {
+        let json = export_to_string(ExportFormat::Json);
+        assert_eq!(
+            json,
+            r#"[
+  {
+    "path": "/bar",
+    "weight": 20.0,
+    "last_access": 1600000000,
+    "visits": 1
+  },
+  {
+    "path": "/foo,baz",
+    "weight": 10.5,
+    "last_access": null,
+    "visits": 0
+  }
+]
+"#
+        );
+
+        // and it reads back as what went in
+        let records: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["path"], "/bar");
+        assert_eq!(records[0]["weight"], 20.0);
+        assert_eq!(records[0]["last_access"], 1600000000);
+        assert_eq!(records[0]["visits"], 1);
+        assert_eq!(records[1]["path"], "/foo,baz");
+        assert_eq!(records[1]["weight"], 10.5);
+        assert!(records[1]["last_access"].is_null());
+        assert_eq!(records[1]["visits"], 0);
+    }
+
+    #[test]
+    fn test_export_jsonl() {
+        assert_eq!(
+            export_to_string(ExportFormat::JsonLines),
+            concat!(
+                r#"{"path":"/bar","weight":20.0,"last_access":1600000000,"visits":1}"#,
+                "\n",
+                r#"{"path":"/foo,baz","weight":10.5,"last_access":null,"visits":0}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_csv() {
+        assert_eq!(
+            export_to_string(ExportFormat::Csv),
+            "path,weight,last_access,visits\n/bar,20.0,1600000000,1\n\"/foo,baz\",10.5,,0\n"
+        );
+    }
+}
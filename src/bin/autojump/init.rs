@@ -0,0 +1,196 @@
+use std::io;
+use std::io::Write;
+use std::str;
+
+use crate::utils::TAB_ENTRY_PATTERN;
+use autojump::Result;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Tcsh,
+    Nushell,
+    Powershell,
+    Elvish,
+    Xonsh,
+}
+
+impl Shell {
+    pub const NAMES: &'static [&'static str] = &[
+        "bash",
+        "zsh",
+        "fish",
+        "tcsh",
+        "nushell",
+        "powershell",
+        "elvish",
+        "xonsh",
+    ];
+
+    fn template(self) -> &'static str {
+        match self {
+            Shell::Bash => include_str!("../../../integrations/init/autojump.bash"),
+            Shell::Zsh => include_str!("../../../integrations/init/autojump.zsh"),
+            Shell::Fish => include_str!("../../../integrations/init/autojump.fish"),
+            Shell::Tcsh => include_str!("../../../integrations/init/autojump.tcsh"),
+            Shell::Nushell => include_str!("../../../integrations/init/autojump.nu"),
+            Shell::Powershell => include_str!("../../../integrations/init/autojump.ps1"),
+            Shell::Elvish => include_str!("../../../integrations/init/autojump.elv"),
+            Shell::Xonsh => include_str!("../../../integrations/init/autojump.xsh"),
+        }
+    }
+}
+
+impl str::FromStr for Shell {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Shell, ()> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "tcsh" => Ok(Shell::Tcsh),
+            "nushell" => Ok(Shell::Nushell),
+            "powershell" => Ok(Shell::Powershell),
+            "elvish" => Ok(Shell::Elvish),
+            "xonsh" => Ok(Shell::Xonsh),
+            _ => Err(()),
+        }
+    }
+}
+
+/// When the current directory is recorded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hook {
+    /// Every time the prompt is shown.
+    Prompt,
+    /// Only when the directory changed.
+    Cd,
+}
+
+impl Hook {
+    fn section(self) -> &'static str {
+        match self {
+            Hook::Prompt => "prompt",
+            Hook::Cd => "cd",
+        }
+    }
+}
+
+impl str::FromStr for Hook {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Hook, ()> {
+        match s {
+            "prompt" => Ok(Hook::Prompt),
+            "cd" => Ok(Hook::Cd),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Returns whether `cmd` is usable as a function name in all shells.
+pub fn is_valid_cmd(cmd: &str) -> bool {
+    let mut chars = cmd.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Keeps the lines between `{{#hook}}` and `{{/hook}}` for the given hook
+/// only, dropping the markers themselves, and substitutes `{{cmd}}` and
+/// `{{tab_entry_pattern}}`.
+fn render(template: &str, cmd: &str, hook: Hook) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut skipping = false;
+    for line in template.split_inclusive('\n') {
+        let marker = line.trim();
+        if let Some(section) = marker
+            .strip_prefix("{{#")
+            .and_then(|s| s.strip_suffix("}}"))
+        {
+            skipping = section != hook.section();
+            continue;
+        }
+        if marker.starts_with("{{/") {
+            skipping = false;
+            continue;
+        }
+        if !skipping {
+            let line = line
+                .replace("{{cmd}}", cmd)
+                .replace("{{tab_entry_pattern}}", TAB_ENTRY_PATTERN);
+            result.push_str(&line);
+        }
+    }
+    result
+}
+
+pub fn init(shell: Shell, cmd: &str, hook: Hook) -> Result<()> {
+    let script = render(shell.template(), cmd, hook);
+    io::stdout().write_all(script.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = "a {{cmd}} {{tab_entry_pattern}}\n{{#prompt}}\nprompt\n{{/prompt}}\n{{#cd}}\ncd\n{{/cd}}\nb\n";
+        let first = |cmd| format!("a {} {}\n", cmd, TAB_ENTRY_PATTERN);
+        assert_eq!(
+            render(template, "j", Hook::Prompt),
+            first("j") + "prompt\nb\n"
+        );
+        assert_eq!(render(template, "x", Hook::Cd), first("x") + "cd\nb\n");
+    }
+
+    #[test]
+    fn test_templates() {
+        for name in Shell::NAMES {
+            let shell: Shell = name.parse().unwrap();
+            for &hook in &[Hook::Prompt, Hook::Cd] {
+                let script = render(shell.template(), "z", hook);
+                assert!(!script.contains("{{"), "{} {:?}", name, hook);
+                assert!(script.contains("--add"), "{} {:?}", name, hook);
+                assert!(
+                    script.contains("AUTOJUMP_ERROR_PATH"),
+                    "{} {:?}",
+                    name,
+                    hook
+                );
+                // tcsh only gets the commands
+                if shell != Shell::Tcsh {
+                    assert!(script.contains("--complete"), "{} {:?}", name, hook);
+                }
+                // shells showing the path of every entry split it out
+                // themselves
+                if [
+                    Shell::Nushell,
+                    Shell::Powershell,
+                    Shell::Elvish,
+                    Shell::Xonsh,
+                ]
+                .contains(&shell)
+                {
+                    assert!(script.contains(TAB_ENTRY_PATTERN), "{} {:?}", name, hook);
+                }
+            }
+        }
+        assert!("sh".parse::<Shell>().is_err());
+    }
+
+    #[test]
+    fn test_is_valid_cmd() {
+        assert!(is_valid_cmd("j"));
+        assert!(is_valid_cmd("_jump-to2"));
+        assert!(!is_valid_cmd(""));
+        assert!(!is_valid_cmd("2j"));
+        assert!(!is_valid_cmd("j;rm"));
+    }
+}
@@ -1,8 +1,18 @@
+use std::env;
+use std::ffi::OsString;
+use std::path;
+
+use clap::error::ErrorKind;
 use clap::{crate_version, value_parser, Arg, ArgAction, Command};
 
-use autojump::Config;
+use autojump::data::{ImportFormat, MergeStrategy, Rewrite};
+use autojump::{Config, Result};
 
+mod export;
+mod init;
 mod manip;
+mod open;
+mod picker;
 mod purge;
 mod query;
 mod stat;
@@ -12,10 +22,25 @@ struct Args {
     arg_dir: Vec<String>,
     flag_complete: bool,
     flag_purge: bool,
-    flag_add: Option<String>,
+    flag_add: Option<path::PathBuf>,
     flag_increase: Option<Option<isize>>,
     flag_decrease: Option<Option<isize>>,
     flag_stat: bool,
+    flag_import: Option<(ImportFormat, path::PathBuf)>,
+    flag_export: Option<export::ExportFormat>,
+    flag_merge: Option<path::PathBuf>,
+    flag_strategy: MergeStrategy,
+    flag_rewrite: Vec<Rewrite>,
+    flag_list: bool,
+    flag_json: bool,
+    flag_interactive: bool,
+    flag_project: bool,
+    flag_within_project: bool,
+    flag_under: Option<path::PathBuf>,
+    flag_children: bool,
+    flag_open: bool,
+    flag_with: Option<Vec<String>>,
+    cmd_init: Option<(init::Shell, String, init::Hook)>,
 }
 
 #[cfg(not(windows))]
@@ -33,36 +58,77 @@ fn check_if_sourced() {
     // no-op on Windows
 }
 
-pub fn main() {
-    check_if_sourced();
+/// Returns whether the command line is a jump to a directory matching `init`
+/// rather than a call of the `init` subcommand, which needs a shell (or a
+/// request for its help).
+fn is_jump_to_init(args: &[OsString]) -> bool {
+    args.get(1).is_some_and(|arg| arg == "init")
+        && !args[2..].iter().any(|arg| {
+            arg.to_str()
+                .is_some_and(|s| init::Shell::NAMES.contains(&s) || s == "-h" || s == "--help")
+        })
+}
+
+fn init_command() -> Command {
+    Command::new("init")
+        .about("print the shell integration script, e.g. for `eval \"$(autojump init bash)\"`")
+        .arg(
+            Arg::new("shell")
+                .required(true)
+                .value_parser(init::Shell::NAMES.to_vec())
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("cmd")
+                .long("cmd")
+                .value_name("NAME")
+                .default_value("j")
+                .action(ArgAction::Set)
+                .help("name of the jump command, also prefixing NAMEc, NAMEo and NAMEco"),
+        )
+        .arg(
+            Arg::new("hook")
+                .long("hook")
+                .value_parser(["prompt", "cd"])
+                .default_value("cd")
+                .action(ArgAction::Set)
+                .help("record the current directory at every prompt, or only when it changed"),
+        )
+}
 
+pub fn main() {
     let args: Args = {
-        let app = Command::new("autojump-rs")
+        let mut cmd = Command::new("autojump-rs")
             .version(crate_version!())
             .about("Automatically jump to directory passed as an argument.")
+            .disable_help_subcommand(true)
             .arg(Arg::new("dir").action(ArgAction::Append))
             .arg(
                 Arg::new("add")
                     .short('a')
                     .long("add")
                     .value_name("DIR")
+                    .value_parser(value_parser!(path::PathBuf))
                     .action(ArgAction::Set)
                     .help("add path"),
             )
             .arg(
                 Arg::new("complete")
                     .long("complete")
+                    .action(ArgAction::SetTrue)
                     .help("used for tab completion"),
             )
             .arg(
                 Arg::new("purge")
                     .long("purge")
+                    .action(ArgAction::SetTrue)
                     .help("remove non-existent paths from database"),
             )
             .arg(
                 Arg::new("stat")
                     .short('s')
                     .long("stat")
+                    .action(ArgAction::SetTrue)
                     .help("show database entries and their key weights"),
             )
             .arg(
@@ -85,7 +151,120 @@ pub fn main() {
                     .num_args(0..=1)
                     .help("decrease current directory weight, default 15"),
             )
-            .get_matches();
+            .arg(
+                Arg::new("import")
+                    .long("import")
+                    .value_names(["FORMAT", "FILE"])
+                    .value_parser(value_parser!(path::PathBuf))
+                    .num_args(2)
+                    .action(ArgAction::Set)
+                    .help("import a database of z, zoxide, fasd or autojump"),
+            )
+            .arg(
+                Arg::new("export")
+                    .long("export")
+                    .value_name("FORMAT")
+                    .value_parser(["json", "jsonl", "csv"])
+                    .action(ArgAction::Set)
+                    .help("print all database entries as json, jsonl or csv"),
+            )
+            .arg(
+                Arg::new("list")
+                    .short('l')
+                    .long("list")
+                    .action(ArgAction::SetTrue)
+                    .help("list all matching directories and why they match"),
+            )
+            .arg(
+                Arg::new("json")
+                    .long("json")
+                    .action(ArgAction::SetTrue)
+                    .help("like --list, but in json"),
+            )
+            .arg(
+                Arg::new("interactive")
+                    .long("interactive")
+                    .action(ArgAction::SetTrue)
+                    .help("choose among several matching directories interactively"),
+            )
+            .arg(
+                Arg::new("project")
+                    .long("project")
+                    .action(ArgAction::SetTrue)
+                    .help("only consider project roots, like git repositories"),
+            )
+            .arg(
+                Arg::new("within_project")
+                    .long("within-project")
+                    .action(ArgAction::SetTrue)
+                    .help("only consider directories in the current project"),
+            )
+            .arg(
+                Arg::new("under")
+                    .long("under")
+                    .value_name("DIR")
+                    .value_parser(value_parser!(path::PathBuf))
+                    .conflicts_with("within_project")
+                    .action(ArgAction::Set)
+                    .help("only consider directories below DIR"),
+            )
+            .arg(
+                Arg::new("children")
+                    .long("children")
+                    .conflicts_with_all(["within_project", "under"])
+                    .action(ArgAction::SetTrue)
+                    .help("only consider directories below the current one"),
+            )
+            .arg(
+                Arg::new("open")
+                    .long("open")
+                    .conflicts_with_all(["list", "json", "interactive"])
+                    .action(ArgAction::SetTrue)
+                    .help("open the directory instead of printing it"),
+            )
+            .arg(
+                Arg::new("with")
+                    .long("with")
+                    .value_name("CMD")
+                    .requires("open")
+                    .action(ArgAction::Set)
+                    .help("command to open the directory with, for --open"),
+            )
+            .arg(
+                Arg::new("merge")
+                    .long("merge")
+                    .value_name("FILE")
+                    .value_parser(value_parser!(path::PathBuf))
+                    .action(ArgAction::Set)
+                    .help("merge another autojump database into this one"),
+            )
+            .arg(
+                Arg::new("strategy")
+                    .long("strategy")
+                    .value_name("STRATEGY")
+                    .value_parser(["max", "sum", "recent"])
+                    .default_value("max")
+                    .requires("merge")
+                    .action(ArgAction::Set)
+                    .help("how to combine weights of paths known to both, for --merge"),
+            )
+            .arg(
+                Arg::new("rewrite")
+                    .long("rewrite")
+                    .value_names(["FROM", "TO"])
+                    .value_parser(value_parser!(path::PathBuf))
+                    .num_args(2)
+                    .requires("merge")
+                    .action(ArgAction::Append)
+                    .help("replace the path prefix FROM by TO, for --merge"),
+            );
+        let argv: Vec<_> = env::args_os().collect();
+        if !is_jump_to_init(&argv) {
+            cmd = cmd.subcommand(init_command());
+        }
+        let app = cmd
+            .try_get_matches_from_mut(argv)
+            .unwrap_or_else(|e| e.exit());
 
         let flag_increase = if app.contains_id("increase") {
             Some(app.get_one::<isize>("increase").copied())
@@ -99,47 +278,174 @@ pub fn main() {
             None
         };
 
+        let flag_import = app.get_many::<path::PathBuf>("import").map(|mut values| {
+            let format = values.next().unwrap();
+            let file = values.next().unwrap();
+            match format.to_str().and_then(|s| s.parse().ok()) {
+                Some(format) => (format, file.clone()),
+                None => cmd
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!(
+                            "unknown import format '{}', expected one of z, zoxide, fasd, autojump",
+                            format.to_string_lossy()
+                        ),
+                    )
+                    .exit(),
+            }
+        });
+
+        let flag_with = app
+            .get_one::<String>("with")
+            .map(|s| match autojump::parse_command(s) {
+                Some(command) => command,
+                None => cmd
+                    .error(ErrorKind::InvalidValue, "the command for --with is empty")
+                    .exit(),
+            });
+
+        let cmd_init = app.subcommand().map(|(_, init)| {
+            let name = init.get_one::<String>("cmd").unwrap();
+            if !init::is_valid_cmd(name) {
+                cmd.find_subcommand_mut("init")
+                    .unwrap()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("invalid command name '{}'", name),
+                    )
+                    .exit();
+            }
+            (
+                init.get_one::<String>("shell").unwrap().parse().unwrap(),
+                name.clone(),
+                init.get_one::<String>("hook").unwrap().parse().unwrap(),
+            )
+        });
+
         Args {
             arg_dir: app
                 .get_many::<String>("dir")
                 .map_or(vec![], |x| x.cloned().collect()),
-            flag_complete: app.contains_id("complete"),
-            flag_purge: app.contains_id("purge"),
-            flag_add: app.get_one::<String>("add").cloned(),
+            flag_complete: app.get_flag("complete"),
+            flag_purge: app.get_flag("purge"),
+            flag_add: app.get_one::<path::PathBuf>("add").cloned(),
             flag_increase,
             flag_decrease,
-            flag_stat: app.contains_id("stat"),
+            flag_stat: app.get_flag("stat"),
+            flag_import,
+            flag_merge: app.get_one::<path::PathBuf>("merge").cloned(),
+            flag_strategy: app.get_one::<String>("strategy").unwrap().parse().unwrap(),
+            flag_rewrite: app
+                .get_many::<path::PathBuf>("rewrite")
+                .map_or(vec![], |x| {
+                    let x: Vec<_> = x.collect();
+                    x.chunks(2).map(|c| Rewrite::new(c[0], c[1])).collect()
+                }),
+            flag_list: app.get_flag("list"),
+            flag_json: app.get_flag("json"),
+            flag_interactive: app.get_flag("interactive"),
+            flag_project: app.get_flag("project"),
+            flag_within_project: app.get_flag("within_project"),
+            flag_under: app.get_one::<path::PathBuf>("under").cloned(),
+            flag_children: app.get_flag("children"),
+            flag_open: app.get_flag("open"),
+            flag_with,
+            cmd_init,
+            flag_export: app.get_one::<String>("export").map(|s| s.parse().unwrap()),
         }
     };
-    let config = Config::defaults();
+
+    // The integration script is what sets things up in the first place.
+    if args.cmd_init.is_none() {
+        check_if_sourced();
+    }
+
+    if let Err(e) = run(args) {
+        eprintln!("autojump: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    if let Some((shell, cmd, hook)) = args.cmd_init {
+        return init::init(shell, &cmd, hook);
+    }
+
+    let config = Config::load()?;
 
     // Process arguments.
     // All arguments are mutually exclusive, so we just check for presence
     // one-by-one.
     if args.flag_complete {
-        query::complete(&config, args.arg_dir);
-        return;
+        return query::complete(&config, args.arg_dir);
     }
-    if args.flag_add.is_some() {
-        manip::add(&config, args.flag_add.unwrap());
-        return;
+    if let Some(dir) = args.flag_add {
+        return manip::add(&config, dir);
     }
     if let Some(weight) = args.flag_increase {
-        manip::increase(&config, weight);
-        return;
+        return manip::increase(&config, weight);
     }
     if let Some(weight) = args.flag_decrease {
-        manip::decrease(&config, weight);
-        return;
+        return manip::decrease(&config, weight);
     }
     if args.flag_purge {
-        purge::purge(&config);
-        return;
+        return purge::purge(&config);
     }
     if args.flag_stat {
-        stat::print_stat(&config);
-        return;
+        return stat::print_stat(&config);
+    }
+    if let Some((format, file)) = args.flag_import {
+        return manip::import(&config, format, file);
+    }
+    if let Some(file) = args.flag_merge {
+        return manip::merge(&config, file, args.flag_strategy, &args.flag_rewrite);
     }
+    if let Some(format) = args.flag_export {
+        return export::export(&config, format);
+    }
+
+    let root = if let Some(dir) = args.flag_under {
+        Some(query::absolute_dir(&dir)?)
+    } else if args.flag_children {
+        Some(utils::current_dir()?)
+    } else if args.flag_within_project {
+        Some(query::current_project(&config)?)
+    } else {
+        None
+    };
+    let scope = query::Scope {
+        projects: args.flag_project,
+        root,
+    };
 
-    query::query(&config, args.arg_dir);
+    if args.flag_list || args.flag_json {
+        return query::list(&config, args.arg_dir, &scope, args.flag_json);
+    }
+    if args.flag_interactive {
+        return query::interactive(&config, args.arg_dir, &scope);
+    }
+    if args.flag_open {
+        return open::open(&config, args.arg_dir, &scope, args.flag_with);
+    }
+
+    query::query(&config, args.arg_dir, &scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_jump_to_init() {
+        let args = |s: &str| -> Vec<OsString> { s.split(' ').map(Into::into).collect() };
+        assert!(is_jump_to_init(&args("autojump init")));
+        assert!(is_jump_to_init(&args("autojump init --children")));
+        assert!(is_jump_to_init(&args("autojump init foo")));
+        assert!(!is_jump_to_init(&args("autojump init bash")));
+        assert!(!is_jump_to_init(&args("autojump init zsh --cmd z")));
+        assert!(!is_jump_to_init(&args("autojump init --hook prompt fish")));
+        assert!(!is_jump_to_init(&args("autojump init --help")));
+        assert!(!is_jump_to_init(&args("autojump foo init")));
+        assert!(!is_jump_to_init(&args("autojump")));
+    }
 }
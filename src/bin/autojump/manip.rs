@@ -1,113 +1,104 @@
-use std::env;
+use std::fs;
 use std::path;
 
+use crate::utils;
 use autojump::data;
-use autojump::data::Entry;
-use autojump::Config;
+use autojump::data::{Entry, ImportFormat, MergeStrategy, Rewrite};
+use autojump::{Config, Error, Result};
 
-const DEFAULT_INCREASE_WEIGHT: isize = 10;
-const DEFAULT_DECREASE_WEIGHT: isize = 15;
-
-fn increase_weight(old_w: f64, inc_w: f64) -> f64 {
-    (old_w.powi(2) + inc_w.powi(2)).sqrt()
-}
-
-fn decrease_weight(old_w: f64, dec_w: f64) -> f64 {
-    let result = old_w - dec_w;
-
-    if result < 0.0 {
-        0.0
-    } else {
-        result
-    }
-}
-
-fn do_increase<P>(entries: &mut Vec<Entry>, p: P, w: f64) -> Entry
+fn do_increase_and_save<P>(config: &Config, p: P, w: f64) -> Result<Entry>
 where
     P: AsRef<path::Path>,
 {
     let p = p.as_ref();
-
-    // don't process $HOME
-    if let Some(home) = dirs::home_dir() {
-        if p == home {
-            // synthesize a fake entry with weight zeroed
-            return Entry::new(p, 0.0);
-        }
+    if config.is_ignored(p) {
+        // synthesize a fake entry with weight zeroed
+        return Ok(Entry::new(p, 0.0));
     }
 
-    for ent in entries.iter_mut() {
-        if ent.path == p {
-            let new_weight = increase_weight(ent.weight, w);
-            ent.weight = new_weight;
-            return ent.clone();
-        }
-    }
-
-    // add the path
-    let entry = Entry::new(p, w);
-    entries.push(entry.clone());
-    entry
+    let now = data::now();
+    data::update(config, |entries| data::increase(entries, p, w, now))
 }
 
-fn do_increase_and_save<P>(config: &Config, p: P, w: f64) -> Entry
+fn do_decrease_and_save<P>(config: &Config, p: P, w: f64) -> Result<Entry>
 where
     P: AsRef<path::Path>,
 {
-    let mut entries = data::load(config);
-    let entry = do_increase(&mut entries, p, w);
-    data::save(config, &entries).unwrap();
-    entry
+    data::update(config, |entries| data::decrease(entries, p, w))
 }
 
-fn do_decrease<P>(entries: &mut Vec<Entry>, p: P, w: f64) -> Entry
+pub fn add<P>(config: &Config, p: P) -> Result<()>
 where
     P: AsRef<path::Path>,
 {
     let p = p.as_ref();
-    for ent in entries.iter_mut() {
-        if ent.path == p {
-            let new_weight = decrease_weight(ent.weight, w);
-            ent.weight = new_weight;
-            return ent.clone();
+    let w = config.increase_weight as f64;
+
+    if config.journal {
+        // Only a cheap append here, the data file is rewritten later.
+        if !config.is_ignored(p) {
+            data::append_journal(config, p, w)?;
         }
+        return Ok(());
     }
 
-    // TODO: original impl also adds an entry in case the requested path is
-    // absent, but is it desirable?
-    // For now let's mimic its behavior...
-    let entry = Entry::new(p, 0.0); // no need to compare
-    entries.push(entry.clone());
-    entry
+    do_increase_and_save(config, p, w)?;
+    Ok(())
 }
 
-fn do_decrease_and_save<P>(config: &Config, p: P, w: f64) -> Entry
-where
-    P: AsRef<path::Path>,
-{
-    let mut entries = data::load(config);
-    let entry = do_decrease(&mut entries, p, w);
-    data::save(config, &entries).unwrap();
-    entry
+pub fn increase(config: &Config, w: Option<isize>) -> Result<()> {
+    let w = w.unwrap_or(config.increase_weight) as f64;
+    let p = utils::current_dir()?;
+    let entry = do_increase_and_save(config, p, w)?;
+    println!("{}", entry);
+    Ok(())
 }
 
-pub fn add<P>(config: &Config, p: P)
+pub fn decrease(config: &Config, w: Option<isize>) -> Result<()> {
+    let w = w.unwrap_or(config.decrease_weight) as f64;
+    let p = utils::current_dir()?;
+    let entry = do_decrease_and_save(config, p, w)?;
+    println!("{}", entry);
+    Ok(())
+}
+
+/// Merges the database of another tool into ours, as if every directory in
+/// it had been visited with the weight it accumulated there.
+pub fn import<P>(config: &Config, format: ImportFormat, file: P) -> Result<()>
 where
     P: AsRef<path::Path>,
 {
-    do_increase_and_save(config, p, DEFAULT_INCREASE_WEIGHT as f64);
-}
+    let file = file.as_ref();
+    let mut f = fs::File::open(file).map_err(|e| Error::file(file, e))?;
+    let imported = data::read_import(format, &mut f, config.increase_weight as f64)
+        .map_err(|e| Error::file(file, e))?;
+
+    let now = data::now();
+    let count = data::update(config, |entries| {
+        let mut count = 0;
+        for entry in imported.iter().filter(|e| !config.is_ignored(&e.path)) {
+            let timestamp = entry.last_access.unwrap_or(now);
+            data::increase(entries, &entry.path, entry.weight, timestamp);
+            count += 1;
+        }
+        count
+    })?;
 
-pub fn increase(config: &Config, w: Option<isize>) {
-    let w = w.unwrap_or(DEFAULT_INCREASE_WEIGHT) as f64;
-    let p = env::current_dir().unwrap();
-    let entry = do_increase_and_save(config, p, w);
-    println!("{}", entry);
+    println!("imported {} entries from {}", count, file.to_string_lossy());
+    Ok(())
 }
 
-pub fn decrease(config: &Config, w: Option<isize>) {
-    let w = w.unwrap_or(DEFAULT_DECREASE_WEIGHT) as f64;
-    let p = env::current_dir().unwrap();
-    let entry = do_decrease_and_save(config, p, w);
-    println!("{}", entry);
+pub fn merge<P>(
+    config: &Config,
+    file: P,
+    strategy: MergeStrategy,
+    rewrites: &[Rewrite],
+) -> Result<()>
+where
+    P: AsRef<path::Path>,
+{
+    let file = file.as_ref();
+    let count = data::merge_file(config, file, strategy, rewrites)?;
+    println!("merged {} entries from {}", count, file.to_string_lossy());
+    Ok(())
 }
@@ -0,0 +1,60 @@
+use std::io;
+use std::path;
+use std::process;
+
+use crate::query;
+use autojump::{Config, Error, Result};
+
+/// Opens the directory a query for `needles` would jump to, with `with` or
+/// the configured opener.
+pub fn open(
+    config: &Config,
+    needles: Vec<String>,
+    scope: &query::Scope,
+    with: Option<Vec<String>>,
+) -> Result<()> {
+    let dir = match query::find(config, needles, scope)? {
+        Some(dir) => dir,
+        None => {
+            let msg = "no matching directory";
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg).into());
+        }
+    };
+
+    let command = with.unwrap_or_else(|| config.opener.clone());
+    // Both are checked to be non-empty.
+    let (program, args) = command.split_first().unwrap();
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(&dir)
+        .status()
+        .map_err(|e| Error::file(program, e))?;
+
+    // `explorer` exits with 1 even when it opened the directory.
+    let succeeded = status.success() || (is_explorer(program) && status.code() == Some(1));
+    if !succeeded {
+        let msg = format!("{} failed with {}", program, status);
+        return Err(io::Error::other(msg).into());
+    }
+    Ok(())
+}
+
+fn is_explorer(program: &str) -> bool {
+    path::Path::new(program)
+        .file_stem()
+        .is_some_and(|s| s.eq_ignore_ascii_case("explorer"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_explorer() {
+        assert!(is_explorer("explorer.exe"));
+        assert!(is_explorer("Explorer"));
+        assert!(is_explorer("C:/Windows/explorer.exe"));
+        assert!(!is_explorer("xdg-open"));
+        assert!(!is_explorer("explorer-ng"));
+    }
+}
@@ -0,0 +1,277 @@
+//! A minimal terminal list for picking one of several candidates.
+//!
+//! The list is drawn on stderr, below the prompt, since stdout is captured
+//! by the shell function that does the actual `cd`.
+
+use std::cmp;
+use std::io;
+use std::io::{IsTerminal, Write};
+use std::path;
+
+use crossterm::cursor::{MoveToColumn, MoveUp};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType};
+
+/// Maximum number of candidates shown at once.
+const MAX_ROWS: usize = 10;
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Selected(usize),
+    Cancelled,
+}
+
+struct Picker<'a> {
+    /// Candidates as displayed, best first.
+    items: Vec<String>,
+    paths: &'a [path::PathBuf],
+    filter: String,
+    /// Indices into `items` of those passing the filter.
+    visible: Vec<usize>,
+    /// Position in `visible` of the highlighted candidate.
+    selected: usize,
+    /// Position in `visible` of the first row shown.
+    offset: usize,
+}
+
+/// Returns whether `item` contains every whitespace-separated word of
+/// `filter`, ignoring case.
+fn matches_filter(item: &str, filter: &str) -> bool {
+    let item = item.to_lowercase();
+    filter
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| item.contains(word))
+}
+
+impl<'a> Picker<'a> {
+    fn new(paths: &'a [path::PathBuf]) -> Picker<'a> {
+        let items = paths
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        let mut picker = Picker {
+            items,
+            paths,
+            filter: String::new(),
+            visible: vec![],
+            selected: 0,
+            offset: 0,
+        };
+        picker.refilter();
+        picker
+    }
+
+    fn refilter(&mut self) {
+        let filter = &self.filter;
+        self.visible = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches_filter(item, filter))
+            .map(|(i, _)| i)
+            .collect();
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let last = self.visible.len() - 1;
+        self.selected = match (down, self.selected) {
+            (true, i) if i == last => 0,
+            (true, i) => i + 1,
+            (false, 0) => last,
+            (false, i) => i - 1,
+        };
+
+        // keep the selection in view
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + MAX_ROWS {
+            self.offset = self.selected + 1 - MAX_ROWS;
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Outcome> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => {
+                return self
+                    .visible
+                    .get(self.selected)
+                    .map(|&i| Outcome::Selected(i));
+            }
+            KeyCode::Esc => return Some(Outcome::Cancelled),
+            KeyCode::Char('c') | KeyCode::Char('g') if ctrl => return Some(Outcome::Cancelled),
+            KeyCode::Up | KeyCode::BackTab => self.move_selection(false),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.move_selection(false),
+            KeyCode::Down | KeyCode::Tab => self.move_selection(true),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.move_selection(true),
+            KeyCode::Char('u') if ctrl => {
+                self.filter.clear();
+                self.refilter();
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.refilter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.filter.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Draws the prompt and the rows below it, leaving the cursor at the end
+    /// of the prompt.
+    fn draw<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // Some terminals don't report their size.
+        let width = match terminal::size() {
+            Ok((cols, _)) if cols > 0 => cols as usize,
+            _ => 80,
+        };
+
+        queue!(
+            w,
+            MoveToColumn(0),
+            Clear(ClearType::FromCursorDown),
+            Print(format!("> {}", self.filter))
+        )?;
+
+        let rows = &self.visible[self.offset..cmp::min(self.offset + MAX_ROWS, self.visible.len())];
+        for (n, &i) in rows.iter().enumerate() {
+            let line: String = self.items[i]
+                .chars()
+                .take(width.saturating_sub(3))
+                .collect();
+            queue!(w, Print("\r\n"))?;
+            if self.offset + n == self.selected {
+                queue!(
+                    w,
+                    SetAttribute(Attribute::Reverse),
+                    Print(format!("> {}", line)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(w, Print(format!("  {}", line)))?;
+            }
+        }
+
+        if !rows.is_empty() {
+            queue!(w, MoveUp(rows.len() as u16))?;
+        }
+        queue!(w, MoveToColumn(2 + self.filter.chars().count() as u16))?;
+        w.flush()
+    }
+
+    fn run<W: Write>(&mut self, w: &mut W) -> io::Result<Outcome> {
+        // Make room for the rows first, so that scrolling doesn't take the
+        // prompt away from where it is drawn.
+        let rows = cmp::min(self.items.len(), MAX_ROWS);
+        queue!(w, Print("\r\n".repeat(rows)))?;
+        if rows > 0 {
+            queue!(w, MoveUp(rows as u16))?;
+        }
+
+        loop {
+            self.draw(w)?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if let Some(outcome) = self.handle_key(key) {
+                    return Ok(outcome);
+                }
+            }
+        }
+    }
+}
+
+/// Lets the user pick one of `paths` on the terminal. Returns `None` if
+/// cancelled.
+///
+/// There is nothing to pick from fewer than two paths, and without a
+/// terminal the first path is taken, like a plain query does.
+pub fn pick(paths: &[path::PathBuf]) -> io::Result<Option<&path::Path>> {
+    if paths.len() < 2 || !io::stderr().is_terminal() {
+        return Ok(paths.first().map(|p| p.as_path()));
+    }
+
+    let mut picker = Picker::new(paths);
+    let mut stderr = io::stderr();
+
+    terminal::enable_raw_mode()?;
+    let outcome = picker.run(&mut stderr);
+    // Clean up even if drawing failed half way.
+    let cleanup = queue!(stderr, MoveToColumn(0), Clear(ClearType::FromCursorDown))
+        .and_then(|_| stderr.flush());
+    terminal::disable_raw_mode()?;
+    cleanup?;
+
+    Ok(match outcome? {
+        Outcome::Selected(i) => Some(picker.paths[i].as_path()),
+        Outcome::Cancelled => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_str(picker: &mut Picker, s: &str) {
+        for c in s.chars() {
+            assert_eq!(picker.handle_key(key(KeyCode::Char(c))), None);
+        }
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        assert!(matches_filter("/home/foo/Projects", ""));
+        assert!(matches_filter("/home/foo/Projects", "proj"));
+        assert!(matches_filter("/home/foo/Projects", "proj foo"));
+        assert!(!matches_filter("/home/foo/Projects", "proj bar"));
+    }
+
+    #[test]
+    fn test_picker() {
+        let paths: Vec<path::PathBuf> = vec!["/foo/bar".into(), "/foo/baz".into(), "/qux".into()];
+        let mut picker = Picker::new(&paths);
+
+        picker.handle_key(key(KeyCode::Down));
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Some(Outcome::Selected(1))
+        );
+        picker.handle_key(key(KeyCode::Up));
+        picker.handle_key(key(KeyCode::Up));
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Some(Outcome::Selected(2))
+        );
+
+        type_str(&mut picker, "bz");
+        assert_eq!(picker.handle_key(key(KeyCode::Enter)), None);
+        picker.handle_key(key(KeyCode::Backspace));
+        assert_eq!(picker.visible, [0, 1]);
+        picker.handle_key(key(KeyCode::Down));
+        assert_eq!(
+            picker.handle_key(key(KeyCode::Enter)),
+            Some(Outcome::Selected(1))
+        );
+
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(picker.handle_key(ctrl_c), Some(Outcome::Cancelled));
+    }
+}
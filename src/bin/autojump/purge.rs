@@ -1,16 +1,53 @@
 use autojump::data;
-use autojump::Config;
+use autojump::data::Entry;
+use autojump::{Config, Result};
 
-pub fn purge(config: &Config) {
-    let entries = data::load(config);
+pub fn purge(config: &Config) -> Result<()> {
+    let purged = data::update(config, |entries| purge_entries(config, entries))?;
+
+    println!("Purged {} entries.", purged);
+    Ok(())
+}
+
+/// Drops the entries of directories that no longer exist or are ignored,
+/// returning how many were dropped.
+fn purge_entries(config: &Config, entries: &mut Vec<Entry>) -> usize {
     let old_len = entries.len();
-    let entries: Vec<_> = entries
-        .into_iter()
-        .filter(|ent| ent.path.exists())
-        .collect();
+    entries.retain(|ent| ent.path.exists() && !config.is_ignored(&ent.path));
+    old_len - entries.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path;
+    use std::process;
+
+    use autojump::Exclusions;
+
+    use super::*;
+
+    #[test]
+    fn test_purge_entries() {
+        let tmp = env::temp_dir();
+        let excluded = tmp.join(format!("autojump-test-{}-purge", process::id()));
+        fs::create_dir_all(&excluded).unwrap();
+
+        let mut config = Config::from_prefix(path::Path::new("/nonexistent"));
+        let pattern = tmp.join("autojump-test-*").to_string_lossy().into_owned();
+        config.exclude = Exclusions::from_patterns(&[pattern]).unwrap();
 
-    data::save(config, &entries).unwrap();
+        let mut entries = vec![
+            Entry::new(tmp.clone(), 10.0),
+            Entry::new(excluded.clone(), 10.0),
+            Entry::new(tmp.join("autojump-nonexistent"), 10.0),
+        ];
+        let purged = purge_entries(&config, &mut entries);
+        fs::remove_dir(&excluded).unwrap();
 
-    let new_len = entries.len();
-    println!("Purged {} entries.", old_len - new_len);
+        assert_eq!(purged, 2);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, tmp);
+    }
 }
@@ -1,14 +1,22 @@
-use std::env;
+use std::collections::HashSet;
+use std::io;
+use std::io::Write;
 use std::path;
 
+use serde_derive::Serialize;
+
+use crate::picker;
 use crate::utils;
 use crate::utils::TabEntryInfo;
 use autojump::data;
-use autojump::matcher::Matcher;
-use autojump::Config;
+use autojump::data::Entry;
+use autojump::matcher::{Filter, Matcher};
+use autojump::project;
+use autojump::{Config, Result};
 
 struct QueryConfig<'a> {
     needles: Vec<&'a str>,
+    filters: Vec<Filter<'a>>,
     check_existence: bool,
     index: usize,
     count: usize,
@@ -20,7 +28,53 @@ enum Query<'a> {
     EarlyResult(path::PathBuf),
 }
 
-pub fn complete(config: &Config, needles: Vec<String>) {
+/// Which of the matching directories are considered.
+#[derive(Default)]
+pub struct Scope {
+    /// Only project roots.
+    pub projects: bool,
+    /// Only this directory and those below it.
+    pub root: Option<path::PathBuf>,
+}
+
+impl Scope {
+    fn admits(&self, config: &Config, p: &path::Path) -> bool {
+        !self.projects || project::is_root(p, &config.project_markers)
+    }
+}
+
+/// Returns `dir` relative to the current directory, with `.` and `..`
+/// resolved.
+///
+/// Symbolic links are left alone, since the database holds paths as the
+/// shell saw them.
+pub fn absolute_dir(dir: &path::Path) -> Result<path::PathBuf> {
+    let mut result = utils::current_dir()?;
+    for c in dir.components() {
+        match c {
+            path::Component::CurDir => {}
+            path::Component::ParentDir => {
+                result.pop();
+            }
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the root of the project the current directory is in.
+pub fn current_project(config: &Config) -> Result<path::PathBuf> {
+    let cwd = utils::current_dir()?;
+    match project::find_root(&cwd, &config.project_markers) {
+        Some(root) => Ok(root.to_path_buf()),
+        None => {
+            let msg = format!("{} is not inside a project", cwd.display());
+            Err(io::Error::new(io::ErrorKind::NotFound, msg).into())
+        }
+    }
+}
+
+pub fn complete(config: &Config, needles: Vec<String>) -> Result<()> {
     // Override needles to only consider the first entry (if present).
     let needle = if needles.is_empty() {
         ""
@@ -29,20 +83,22 @@ pub fn complete(config: &Config, needles: Vec<String>) {
     };
     let needles = vec![needle];
 
-    match prepare_query(&needles, false, 9, false) {
+    match prepare_query(&needles, false, config.completion_count, false) {
         Query::Execute(query) => {
-            let real_needle = query.needles[0].clone();
-            let result = do_query(config, query);
-            // Convert to `&str` for tab entry info creation.
+            let real_needle = query.needles[0];
+            let result = do_query(config, query, &Scope::default())?;
+            // Convert to `&str` for tab entry info creation, escaping
+            // anything that isn't valid Unicode the same way as in the data
+            // file, so that it can be decoded when coming back.
             let result: Vec<_> = result
-                .into_iter()
-                .map(|p| p.to_string_lossy().into_owned())
+                .iter()
+                .map(|p| data::encode_path(p).into_owned())
                 .collect();
             let strs: Vec<_> = result.iter().map(|p| p.as_str()).collect();
             // Directly print out the directory if it's the only entry.
             if strs.len() == 1 {
                 println!("{}", strs[0]);
-                return;
+                return Ok(());
             }
             // Output the tab completion menu
             let tab_entries = TabEntryInfo::from_matches(real_needle, &strs);
@@ -51,18 +107,30 @@ pub fn complete(config: &Config, needles: Vec<String>) {
             }
         }
         Query::EarlyResult(path) => {
-            println!("{}", path.to_string_lossy());
+            utils::print_path(&path)?;
         }
     }
+
+    Ok(())
 }
 
-pub fn query(config: &Config, needles: Vec<String>) {
+pub fn query(config: &Config, needles: Vec<String>, scope: &Scope) -> Result<()> {
     let needles: Vec<_> = needles.iter().map(|s| s.as_str()).collect();
     let result = match prepare_query(&needles, true, 1, true) {
-        Query::Execute(query) => do_query(config, query).iter().next().unwrap().clone(),
+        Query::Execute(query) => do_query(config, query, scope)?.first().unwrap().clone(),
         Query::EarlyResult(path) => path,
     };
-    println!("{}", result.to_string_lossy());
+    utils::print_path(&result)?;
+    Ok(())
+}
+
+/// Returns the directory a query for `needles` jumps to, if any.
+pub fn find(config: &Config, needles: Vec<String>, scope: &Scope) -> Result<Option<path::PathBuf>> {
+    let needles: Vec<_> = needles.iter().map(|s| s.as_str()).collect();
+    Ok(match prepare_query(&needles, true, 1, false) {
+        Query::Execute(query) => do_query(config, query, scope)?.into_iter().next(),
+        Query::EarlyResult(path) => Some(path),
+    })
 }
 
 fn prepare_query<'a>(
@@ -72,25 +140,22 @@ fn prepare_query<'a>(
     use_fallback: bool,
 ) -> Query<'a> {
     let mut count = count;
-    let needles = if needles.is_empty() {
-        vec![""]
-    } else {
-        utils::sanitize(needles)
-    };
+    let utils::Needles { plain, filters } = utils::parse_needles(needles);
+    let needles = if plain.is_empty() { vec![""] } else { plain };
 
     // Try to parse the first needle (command-line argument) as tab entry
     // spec.
     let tab = utils::get_tab_entry_info(needles[0]);
-    if tab.path.is_some() {
+    if let Some(tab_path) = tab.path {
         // Just trust the auto-completion, like the original impl does.
-        let result = path::Path::new(tab.path.unwrap()).to_path_buf();
+        let result = data::decode_path(tab_path.as_bytes());
         return Query::EarlyResult(result);
     }
 
     // Override query needles if tab entry is found, also set the index
     // requested.
     let index;
-    let needles = if tab.index.is_some() {
+    let needles = if let Some(tab_index) = tab.index {
         // process "foo__" and "foo__1" differently
         if tab.index_explicit {
             // explicit match requested, override count
@@ -98,7 +163,7 @@ fn prepare_query<'a>(
         }
 
         // index is 1-based on the command line!
-        index = tab.index.unwrap() - 1;
+        index = tab_index - 1;
         vec![tab.needle.unwrap()]
     } else {
         index = 0;
@@ -107,6 +172,7 @@ fn prepare_query<'a>(
 
     Query::Execute(QueryConfig {
         needles,
+        filters,
         check_existence,
         index,
         count,
@@ -114,25 +180,56 @@ fn prepare_query<'a>(
     })
 }
 
-fn do_query<'a>(config: &Config, query: QueryConfig<'a>) -> Vec<path::PathBuf> {
+fn build_matcher<'a>(
+    config: &Config,
+    needles: Vec<&'a str>,
+    filters: &[Filter],
+    scope: &Scope,
+) -> Result<Matcher<'a>> {
+    let matcher =
+        Matcher::new_smartcase_with_options(needles, &config.matcher).with_filters(filters)?;
+    Ok(match &scope.root {
+        Some(root) => matcher.under(root.clone()),
+        None => matcher,
+    })
+}
+
+/// Loads the entries, best ranked first.
+fn load_ranked(config: &Config, now: u64) -> Result<Vec<Entry>> {
+    // Take the chance to fold pending journal records into the data file.
+    data::try_compact(config)?;
+
+    let mut entries = data::load(config)?;
+    // Default order is ascending, but apparently we want to match the
+    // other way around.
+    entries.sort_by(|a, b| {
+        let score_a = config.ranking.score(a, now);
+        let score_b = config.ranking.score(b, now);
+        score_b.total_cmp(&score_a)
+    });
+    Ok(entries)
+}
+
+fn do_query<'a>(
+    config: &Config,
+    query: QueryConfig<'a>,
+    scope: &Scope,
+) -> Result<Vec<path::PathBuf>> {
     let needles = query.needles;
     let check_existence = query.check_existence;
     let index = query.index;
     let count = query.count;
 
-    let mut entries = data::load(config);
-    // Default order is ascending, but apparently we want to match the
-    // other way around.
-    entries.sort_by(|a, b| b.cmp(a));
-
-    let matcher = Matcher::new_smartcase(needles);
-    let result = matcher.execute(&entries);
+    let now = data::now();
+    let entries = load_ranked(config, now)?;
+    let matcher = build_matcher(config, needles, &query.filters, scope)?;
+    let result = matcher
+        .rank(&entries, |e| config.ranking.score(e, now))
+        .into_iter()
+        .map(|m| m.item);
 
     // Filter out cwd and (when requested) non-existent directories.
-    let cwd: Option<_> = match env::current_dir() {
-        Ok(cwd) => Some(cwd),
-        Err(_) => None,
-    };
+    let cwd: Option<_> = utils::current_dir().ok();
     let mut result: Vec<_> = result
         .filter(|p| {
             if let Some(cwd) = &cwd {
@@ -141,6 +238,7 @@ fn do_query<'a>(config: &Config, query: QueryConfig<'a>) -> Vec<path::PathBuf> {
                 true
             }
         })
+        .filter(|p| scope.admits(config, &p.path))
         .filter(|p| {
             if check_existence {
                 p.path.exists()
@@ -156,5 +254,154 @@ fn do_query<'a>(config: &Config, query: QueryConfig<'a>) -> Vec<path::PathBuf> {
         result.push(".".into())
     }
 
-    result
+    Ok(result)
+}
+
+/// A candidate of `--list`, in the order of preference.
+#[derive(Serialize)]
+struct Candidate<'a> {
+    /// Escaped like in the text data file if not valid Unicode.
+    path: String,
+    weight: f64,
+    /// What the candidates are ordered by, depending on the ranking and the
+    /// matcher mode in use.
+    score: f64,
+    /// The first matcher stage that accepted the path.
+    kind: &'static str,
+    similarity: Option<f64>,
+    #[serde(skip)]
+    entry: &'a Entry,
+}
+
+/// Returns every existing directory among `entries` matching `needles`,
+/// except the current one, along with why it matched.
+fn find_candidates<'e>(
+    config: &Config,
+    entries: &'e [Entry],
+    needles: &[String],
+    scope: &Scope,
+    now: u64,
+) -> Result<Vec<Candidate<'e>>> {
+    let utils::Needles { plain, filters } = utils::parse_needles(needles);
+    let needles = if plain.is_empty() { vec![""] } else { plain };
+    let matcher = build_matcher(config, needles, &filters, scope)?;
+
+    let cwd: Option<_> = utils::current_dir().ok();
+    let mut candidates: Vec<Candidate> = vec![];
+    let mut seen = HashSet::new();
+    for m in matcher.rank(entries, |e| config.ranking.score(e, now)) {
+        let entry = m.item;
+        // Only the first, i.e. best, reason for a path to match is kept.
+        if seen.contains(&entry.path) {
+            continue;
+        }
+        if cwd.as_ref() == Some(&entry.path) || !entry.path.exists() {
+            continue;
+        }
+        if !scope.admits(config, &entry.path) {
+            continue;
+        }
+        seen.insert(&entry.path);
+
+        candidates.push(Candidate {
+            path: data::encode_path(&entry.path).into_owned(),
+            weight: entry.weight,
+            score: m.score,
+            kind: m.kind.as_str(),
+            similarity: m.similarity,
+            entry,
+        });
+    }
+
+    Ok(candidates)
+}
+
+/// Prints every existing directory matching `needles`, and why it matched,
+/// for consumption by pickers.
+pub fn list(config: &Config, needles: Vec<String>, scope: &Scope, json: bool) -> Result<()> {
+    let now = data::now();
+    let entries = load_ranked(config, now)?;
+    let candidates = find_candidates(config, &entries, &needles, scope, now)?;
+
+    let mut stdout = io::stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut stdout, &candidates).map_err(io::Error::from)?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+
+    for c in candidates.iter() {
+        write!(
+            stdout,
+            "{:.1}\t{}\t{:.3}\t",
+            c.score,
+            c.kind,
+            c.similarity.unwrap_or(0.0)
+        )?;
+        utils::write_path(&mut stdout, &c.entry.path)?;
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
+
+/// Lets the user pick among the matching directories, if there are several.
+pub fn interactive(config: &Config, needles: Vec<String>, scope: &Scope) -> Result<()> {
+    let now = data::now();
+    let entries = load_ranked(config, now)?;
+    let paths: Vec<_> = find_candidates(config, &entries, &needles, scope, now)?
+        .into_iter()
+        .map(|c| c.entry.path.clone())
+        .collect();
+
+    // Stay where we are if nothing is picked, like a query does when
+    // nothing matches.
+    let result = picker::pick(&paths)?.unwrap_or_else(|| path::Path::new("."));
+    utils::print_path(result)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absolute_dir() {
+        let cwd = utils::current_dir().unwrap();
+        let p = |s| absolute_dir(path::Path::new(s)).unwrap();
+
+        assert_eq!(p("."), cwd);
+        assert_eq!(p("foo/./bar/../baz"), cwd.join("foo").join("baz"));
+        assert_eq!(p(".."), cwd.parent().unwrap());
+        #[cfg(unix)]
+        assert_eq!(p("/foo/../bar/"), path::Path::new("/bar"));
+    }
+
+    #[test]
+    fn test_prepare_query_tab_entry() {
+        let early_result = |needle| match prepare_query(&[needle], true, 1, true) {
+            Query::EarlyResult(p) => Some(p),
+            Query::Execute(_) => None,
+        };
+
+        // entries printed by --complete, or made up from a unique match by
+        // the integration scripts, jump right to their path
+        assert_eq!(early_result("foo__2__/foo/bar"), Some("/foo/bar".into()));
+        assert_eq!(early_result("foo____/foo/bar"), Some("/foo/bar".into()));
+        assert_eq!(early_result("__1__/foo__bar"), Some("/foo__bar".into()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let p = path::Path::new(std::ffi::OsStr::from_bytes(b"/foo/\xff"));
+            let entry = format!("foo__1__{}", data::encode_path(p));
+            assert_eq!(early_result(&entry).as_deref(), Some(p));
+        }
+
+        match prepare_query(&["foo__2"], true, 9, true) {
+            Query::Execute(query) => {
+                assert_eq!(query.needles, ["foo"]);
+                assert_eq!((query.index, query.count), (1, 1));
+            }
+            Query::EarlyResult(_) => panic!("foo__2 is not a path"),
+        }
+    }
 }
@@ -1,18 +1,16 @@
-use std::env;
-
+use crate::utils;
 use autojump::data;
-use autojump::Config;
+use autojump::{Config, Result};
 
-pub fn print_stat(config: &Config) {
-    let cwd: Option<_> = match env::current_dir() {
-        Ok(dir) => Some(dir),
-        // The cwd is gone or inaccessible, disable weight reporting later.
-        Err(_) => None,
-    };
+pub fn print_stat(config: &Config) -> Result<()> {
+    // If the cwd is gone or inaccessible, disable weight reporting later.
+    let cwd: Option<_> = utils::current_dir().ok();
     let mut cwd_weight: Option<f64> = None;
 
+    data::try_compact(config)?;
+
     let entries = {
-        let mut tmp = data::load(config);
+        let mut tmp = data::load(config)?;
         tmp.sort();
         tmp
     };
@@ -25,10 +23,8 @@ pub fn print_stat(config: &Config) {
 
         // Simultaneously check for current directory's weight, if current
         // directory is accessible.
-        if cwd.is_some() && cwd_weight.is_none() {
-            if &entry.path == cwd.as_ref().unwrap() {
-                cwd_weight = Some(entry.weight);
-            }
+        if cwd_weight.is_none() && cwd.as_ref() == Some(&entry.path) {
+            cwd_weight = Some(entry.weight);
         }
     }
 
@@ -42,4 +38,5 @@ pub fn print_stat(config: &Config) {
     }
 
     println!("\ndata:\t {}", config.data_path.to_string_lossy());
+    Ok(())
 }
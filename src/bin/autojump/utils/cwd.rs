@@ -0,0 +1,70 @@
+use std::env;
+use std::ffi;
+use std::fs;
+use std::io;
+use std::path;
+
+/// Returns the current directory as the shell sees it.
+///
+/// `env::current_dir` resolves symbolic links, while the shell integrations
+/// record `$PWD`, which keeps them. `$PWD` is trusted as long as it still
+/// names the current directory, as it may be stale if the shell didn't set
+/// it for us.
+pub fn current_dir() -> io::Result<path::PathBuf> {
+    let physical = env::current_dir()?;
+    Ok(logical_dir(env::var_os("PWD"), physical))
+}
+
+fn logical_dir(pwd: Option<ffi::OsString>, physical: path::PathBuf) -> path::PathBuf {
+    match pwd.map(path::PathBuf::from) {
+        Some(pwd) if is_plain_absolute(&pwd) && is_same_dir(&pwd, &physical) => pwd,
+        _ => physical,
+    }
+}
+
+/// Returns whether `p` is absolute and has no `.` or `..` components.
+fn is_plain_absolute(p: &path::Path) -> bool {
+    p.is_absolute()
+        && p.components()
+            .all(|c| !matches!(c, path::Component::CurDir | path::Component::ParentDir))
+}
+
+fn is_same_dir(a: &path::Path, b: &path::Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_logical_dir() {
+        let tmp = env::temp_dir()
+            .join("autojump-test-cwd")
+            .join(std::process::id().to_string());
+        let real = tmp.join("real");
+        let other = tmp.join("other");
+        let link = tmp.join("link");
+        fs::create_dir_all(&real).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        if fs::symlink_metadata(&link).is_err() {
+            std::os::unix::fs::symlink(&real, &link).unwrap();
+        }
+        let physical = fs::canonicalize(&real).unwrap();
+        let logical = |pwd: &path::Path| logical_dir(Some(pwd.into()), physical.clone());
+
+        // the path through the symbolic link is kept
+        assert_eq!(logical(&link), link);
+        assert_eq!(logical(&physical), physical);
+        // unless it doesn't lead here
+        assert_eq!(logical(&other), physical);
+        assert_eq!(logical(&tmp.join("gone")), physical);
+        assert_eq!(logical(&link.join("..").join("link")), physical);
+        assert_eq!(logical(path::Path::new("link")), physical);
+        assert_eq!(logical_dir(None, physical.clone()), physical);
+    }
+}
@@ -1,5 +1,15 @@
 use std::path;
 
+use autojump::matcher::{Filter, FilterKind};
+
+/// Needles from the command line, split into those that are matched and
+/// those that only filter the matches.
+#[derive(Debug, PartialEq)]
+pub struct Needles<'a> {
+    pub plain: Vec<&'a str>,
+    pub filters: Vec<Filter<'a>>,
+}
+
 fn sanitize_one_needle(needle: &str) -> &str {
     if needle == path::MAIN_SEPARATOR.to_string() {
         needle
@@ -8,15 +18,55 @@ fn sanitize_one_needle(needle: &str) -> &str {
     }
 }
 
-pub fn sanitize<'a, S>(needles: &'a [S]) -> Vec<&'a str>
+/// Returns the filter spelled by `needle`, or `None` for a plain needle.
+///
+/// `re:` and `glob:` prefixes give a regular expression or a shell pattern
+/// for the whole path, and a leading `!` rejects the paths matching the
+/// rest of the needle instead.
+fn parse_filter(needle: &str) -> Option<Filter<'_>> {
+    let (negated, rest) = match needle.strip_prefix('!') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, needle),
+    };
+
+    let (kind, pattern) = if let Some(pattern) = rest.strip_prefix("re:") {
+        (FilterKind::Regex, pattern)
+    } else if let Some(pattern) = rest.strip_prefix("glob:") {
+        (FilterKind::Glob, pattern)
+    } else if negated {
+        (FilterKind::Plain, sanitize_one_needle(rest))
+    } else {
+        return None;
+    };
+
+    Some(Filter {
+        kind,
+        pattern,
+        negated,
+    })
+}
+
+pub fn parse_needles<S>(needles: &[S]) -> Needles<'_>
 where
     S: AsRef<str>,
 {
-    needles
-        .iter()
-        .map(|s| s.as_ref())
-        .map(sanitize_one_needle)
-        .collect()
+    let mut plain = vec![];
+    let mut filters = vec![];
+    for s in needles.iter().map(|s| s.as_ref()) {
+        match parse_filter(s) {
+            Some(filter) => filters.push(filter),
+            None => plain.push(s),
+        }
+    }
+
+    Needles {
+        plain: sanitize(&plain),
+        filters,
+    }
+}
+
+pub fn sanitize<'a>(needles: &[&'a str]) -> Vec<&'a str> {
+    needles.iter().map(|s| sanitize_one_needle(s)).collect()
 }
 
 #[cfg(test)]
@@ -45,6 +95,29 @@ mod tests {
         assert_eq!(sanitize_one_needle("a\\\\"), "a");
     }
 
+    #[test]
+    fn test_parse_needles() {
+        let filter = |kind, pattern, negated| Filter {
+            kind,
+            pattern,
+            negated,
+        };
+
+        let a: [&str; 0] = [];
+        assert_eq!(parse_needles(&a).plain, a);
+        assert_eq!(
+            parse_needles(&["foo", "re:-api$", "!target", "bar", "!glob:*/src/*", "!"]),
+            Needles {
+                plain: vec!["foo", "bar", "!"],
+                filters: vec![
+                    filter(FilterKind::Regex, "-api$", false),
+                    filter(FilterKind::Plain, "target", true),
+                    filter(FilterKind::Glob, "*/src/*", true),
+                ],
+            }
+        );
+    }
+
     #[cfg(not(windows))]
     #[test]
     fn test_sanitize() {
@@ -1,7 +1,11 @@
+mod cwd;
 mod input;
+mod output;
 mod shells;
 mod tabentry;
 
+pub use self::cwd::*;
 pub use self::input::*;
+pub use self::output::*;
 pub use self::shells::*;
 pub use self::tabentry::*;
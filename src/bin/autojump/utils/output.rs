@@ -0,0 +1,23 @@
+use std::io;
+use std::io::Write;
+use std::path;
+
+/// Writes `p` byte for byte, so that the shell can `cd` into it even if it
+/// isn't valid Unicode.
+#[cfg(unix)]
+pub fn write_path(w: &mut dyn Write, p: &path::Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    w.write_all(p.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn write_path(w: &mut dyn Write, p: &path::Path) -> io::Result<()> {
+    w.write_all(p.to_string_lossy().as_bytes())
+}
+
+/// Prints `p` on a line of its own.
+pub fn print_path(p: &path::Path) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    write_path(&mut stdout, p)?;
+    stdout.write_all(b"\n")
+}
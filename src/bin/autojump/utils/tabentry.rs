@@ -10,14 +10,14 @@ pub struct TabEntryInfo<'a> {
 
 impl<'a> fmt::Display for TabEntryInfo<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.needle.is_some() {
-            write!(f, "{}", self.needle.unwrap())?;
+        if let Some(needle) = self.needle {
+            write!(f, "{}", needle)?;
         }
-        if self.index.is_some() {
-            write!(f, "__{}", self.index.unwrap())?;
+        if let Some(index) = self.index {
+            write!(f, "__{}", index)?;
         }
-        if self.path.is_some() {
-            write!(f, "__{}", self.path.unwrap())?;
+        if let Some(path) = self.path {
+            write!(f, "__{}", path)?;
         }
         Ok(())
     }
@@ -42,6 +42,12 @@ impl<'a> TabEntryInfo<'a> {
     }
 }
 
+/// Splits a tab entry into needle, index and path like `get_tab_entry_info`,
+/// for the integration scripts that show the path of every entry. Only
+/// syntax understood by the regex engines of Nushell, .NET, Go and Python
+/// is used.
+pub const TAB_ENTRY_PATTERN: &str = r"^(.*?)__(.*?)__(.*)$";
+
 /// Given a tab entry in the following format return needle, index, and path:
 ///
 /// ```ignore
@@ -93,7 +99,7 @@ fn get_tab_entry_info_internal<'a>(entry: &'a str, separator: &'a str) -> TabEnt
                 // Handle "foo__" as if the missing index is 1.
                 // Put the logic here for better locality (the original impl has
                 // it in the driver script).
-                if remaining.len() == 0 {
+                if remaining.is_empty() {
                     // fxxk the borrow checker
                     // index = Some(1);
                     parse_index("1", false);
@@ -147,6 +153,33 @@ mod tests {
         assert_tab_entry_info!("a____b", Some("a"), None, false, Some("b"));
     }
 
+    #[test]
+    fn test_tab_entry_pattern() {
+        let re = regex::Regex::new(TAB_ENTRY_PATTERN).unwrap();
+        let matches = ["/foo/bar", "/foo__bar/baz", r"/foo/\xff"];
+        let mut entries: Vec<_> = TabEntryInfo::from_matches("foo", &matches)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        // what the scripts make of a unique match
+        entries.push("foo__1__/foo/bar".to_string());
+        entries.push("foo____/foo/bar".to_string());
+        entries.push("__1__/foo/bar".to_string());
+
+        for entry in &entries {
+            let info = get_tab_entry_info(entry);
+            let caps = re.captures(entry).unwrap();
+            assert_eq!(info.needle, Some(&caps[1]), "{}", entry);
+            assert_eq!(info.path, Some(&caps[3]), "{}", entry);
+        }
+        assert_eq!(&re.captures(&entries[1]).unwrap()[3], matches[1]);
+
+        for entry in &["foo", "foo__", "foo__2"] {
+            assert!(!re.is_match(entry));
+            assert_eq!(get_tab_entry_info(entry).path, None);
+        }
+    }
+
     #[test]
     fn test_tab_entry_info_parse_malformed_deviations() {
         // Original impl: Some("a"), Some(0), None
@@ -0,0 +1,96 @@
+use std::path;
+
+use glob;
+
+enum Rule {
+    /// Plain paths exclude themselves and everything below them.
+    Prefix(path::PathBuf),
+    /// Patterns containing wildcards are matched against the whole path.
+    /// `*` also matches path separators, so `/tmp/*` covers all of `/tmp`.
+    Glob(glob::Pattern),
+}
+
+impl Rule {
+    fn parse(s: &str) -> Result<Rule, glob::PatternError> {
+        if s.contains(['*', '?', '[']) {
+            Ok(Rule::Glob(glob::Pattern::new(s)?))
+        } else {
+            Ok(Rule::Prefix(path::PathBuf::from(s)))
+        }
+    }
+
+    fn matches(&self, p: &path::Path) -> bool {
+        match self {
+            Rule::Prefix(prefix) => p.starts_with(prefix),
+            Rule::Glob(pattern) => pattern.matches_path(p),
+        }
+    }
+}
+
+/// A set of rules for directories that should never be recorded.
+pub struct Exclusions {
+    rules: Vec<Rule>,
+}
+
+impl Exclusions {
+    pub fn empty() -> Exclusions {
+        Exclusions { rules: vec![] }
+    }
+
+    pub fn from_patterns<S>(patterns: &[S]) -> Result<Exclusions, glob::PatternError>
+    where
+        S: AsRef<str>,
+    {
+        let rules = patterns
+            .iter()
+            .map(|s| Rule::parse(s.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(Exclusions { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn matches<P>(&self, p: P) -> bool
+    where
+        P: AsRef<path::Path>,
+    {
+        let p = p.as_ref();
+        self.rules.iter().any(|rule| rule.matches(p))
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusions() {
+        let ex = Exclusions::from_patterns(&["/tmp/*", "/mnt/nfs", "*/node_modules/*", "*/.git"])
+            .unwrap();
+
+        assert!(ex.matches("/tmp/foo"));
+        assert!(ex.matches("/tmp/foo/bar"));
+        assert!(!ex.matches("/tmp"));
+        assert!(!ex.matches("/tmpfoo"));
+
+        assert!(ex.matches("/mnt/nfs"));
+        assert!(ex.matches("/mnt/nfs/share"));
+        assert!(!ex.matches("/mnt/nfs2"));
+
+        assert!(ex.matches("/src/app/node_modules/foo"));
+        assert!(!ex.matches("/src/app/node_modules"));
+
+        assert!(ex.matches("/src/app/.git"));
+        assert!(!ex.matches("/src/app/.github"));
+
+        assert!(!ex.matches("/home/foo/src"));
+        assert!(!Exclusions::empty().matches("/tmp/foo"));
+    }
+
+    #[test]
+    fn test_exclusions_invalid() {
+        assert!(Exclusions::from_patterns(&["/foo/[bar"]).is_err());
+    }
+}
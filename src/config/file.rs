@@ -0,0 +1,215 @@
+use std::fs;
+
+use serde_derive::Deserialize;
+
+use super::{parse_command, Backend, Config, Exclusions, Ranking};
+use crate::matcher::{Algorithm, MatchMode};
+use crate::Error;
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+struct AgingSection {
+    max_total_weight: Option<f64>,
+    max_entries: Option<usize>,
+    factor: Option<f64>,
+    min_weight: Option<f64>,
+}
+
+/// On-disk representation of the config file. Every key is optional, and
+/// only the keys present override the corresponding setting.
+///
+/// ```toml
+/// increase_weight = 10
+/// decrease_weight = 15
+/// backup_threshold = 86400
+/// fuzzy_threshold = 0.6
+/// fuzzy_algorithm = "subsequence"
+/// match_mode = "scored"
+/// fold_accents = true
+/// completion_count = 9
+/// lock_timeout = 1000
+/// journal = true
+/// journal_max_size = 65536
+/// ranking = "frecency"
+/// backend = "binary"
+/// exclude = ["/tmp/*", "*/node_modules/*"]
+/// project_markers = [".git", "Cargo.toml"]
+/// opener = "code --new-window"
+///
+/// [aging]
+/// max_total_weight = 10000
+/// max_entries = 1000
+/// ```
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    increase_weight: Option<isize>,
+    decrease_weight: Option<isize>,
+    backup_threshold: Option<u64>,
+    fuzzy_threshold: Option<f64>,
+    fuzzy_algorithm: Option<Algorithm>,
+    match_mode: Option<MatchMode>,
+    fold_accents: Option<bool>,
+    completion_count: Option<usize>,
+    lock_timeout: Option<u64>,
+    journal: Option<bool>,
+    journal_max_size: Option<u64>,
+    ranking: Option<Ranking>,
+    backend: Option<Backend>,
+    exclude: Option<Vec<String>>,
+    project_markers: Option<Vec<String>>,
+    opener: Option<String>,
+    aging: Option<AgingSection>,
+}
+
+impl ConfigFile {
+    fn apply(self, config: &mut Config) -> Result<(), glob::PatternError> {
+        if let Some(w) = self.increase_weight {
+            config.increase_weight = w;
+        }
+        if let Some(w) = self.decrease_weight {
+            config.decrease_weight = w;
+        }
+        if let Some(t) = self.backup_threshold {
+            config.backup_threshold = t;
+        }
+        if let Some(t) = self.fuzzy_threshold {
+            config.matcher.fuzzy_threshold = t;
+        }
+        if let Some(algorithm) = self.fuzzy_algorithm {
+            config.matcher.fuzzy_algorithm = algorithm;
+        }
+        if let Some(mode) = self.match_mode {
+            config.matcher.mode = mode;
+        }
+        if let Some(b) = self.fold_accents {
+            config.matcher.fold_accents = b;
+        }
+        if let Some(n) = self.completion_count {
+            config.completion_count = n;
+        }
+        if let Some(t) = self.lock_timeout {
+            config.lock_timeout = t;
+        }
+        if let Some(b) = self.journal {
+            config.journal = b;
+        }
+        if let Some(n) = self.journal_max_size {
+            config.journal_max_size = n;
+        }
+        if let Some(ranking) = self.ranking {
+            config.ranking = ranking;
+        }
+        if let Some(backend) = self.backend {
+            config.set_backend(backend);
+        }
+        if let Some(patterns) = self.exclude {
+            config.exclude = Exclusions::from_patterns(&patterns)?;
+        }
+        if let Some(markers) = self.project_markers {
+            config.project_markers = markers;
+        }
+        if let Some(command) = self.opener.as_deref().and_then(parse_command) {
+            config.opener = command;
+        }
+
+        if let Some(aging) = self.aging {
+            if aging.max_total_weight.is_some() {
+                config.aging.max_total_weight = aging.max_total_weight;
+            }
+            if aging.max_entries.is_some() {
+                config.aging.max_entries = aging.max_entries;
+            }
+            if let Some(factor) = aging.factor {
+                config.aging.factor = factor;
+            }
+            if let Some(w) = aging.min_weight {
+                config.aging.min_weight = w;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse(s: &str) -> Result<ConfigFile, toml::de::Error> {
+    toml::from_str(s)
+}
+
+pub fn load(config: &mut Config) -> crate::Result<()> {
+    let path = config.config_path.clone();
+    let s = fs::read_to_string(&path).map_err(|e| Error::file(&path, e))?;
+
+    let file = parse(&s).map_err(|e| Error::Config(path.clone(), e.to_string()))?;
+    file.apply(config)
+        .map_err(|e| Error::Config(path, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path;
+
+    #[test]
+    fn test_parse_empty() {
+        let mut config = Config::from_prefix(path::Path::new("/nonexistent"));
+        parse("").unwrap().apply(&mut config).unwrap();
+        assert_eq!(config.increase_weight, 10);
+        assert_eq!(config.decrease_weight, 15);
+        assert_eq!(config.completion_count, 9);
+        assert_eq!(config.ranking, Ranking::Weight);
+        assert_eq!(config.aging.max_entries, None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut config = Config::from_prefix(path::Path::new("/nonexistent"));
+        let file = parse(
+            r#"
+increase_weight = 20
+fuzzy_threshold = 0.8
+fuzzy_algorithm = "damerau-levenshtein"
+match_mode = "scored"
+fold_accents = true
+completion_count = 5
+ranking = "frecency"
+exclude = ["/tmp/*"]
+project_markers = [".git"]
+opener = "code  --new-window"
+
+[aging]
+max_entries = 100
+"#,
+        )
+        .unwrap();
+        file.apply(&mut config).unwrap();
+
+        assert_eq!(config.increase_weight, 20);
+        assert_eq!(config.decrease_weight, 15);
+        assert_eq!(config.matcher.fuzzy_threshold, 0.8);
+        assert_eq!(
+            config.matcher.fuzzy_algorithm,
+            Algorithm::DamerauLevenshtein
+        );
+        assert_eq!(config.matcher.mode, MatchMode::Scored);
+        assert!(config.matcher.fold_accents);
+        assert_eq!(config.completion_count, 5);
+        assert_eq!(config.ranking, Ranking::Frecency);
+        assert_eq!(config.aging.max_entries, Some(100));
+        assert_eq!(config.aging.max_total_weight, None);
+        assert!(!config.exclude.is_empty());
+        assert_eq!(config.project_markers, [".git"]);
+        assert_eq!(config.opener, ["code", "--new-window"]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("increase_weight = \"a lot\"").is_err());
+        assert!(parse("no_such_key = 1").is_err());
+        assert!(parse("ranking = \"random\"").is_err());
+
+        let mut config = Config::from_prefix(path::Path::new("/nonexistent"));
+        let file = parse("exclude = [\"/foo/[bar\"]").unwrap();
+        assert!(file.apply(&mut config).is_err());
+    }
+}
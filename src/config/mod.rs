@@ -1,9 +1,180 @@
+mod exclude;
+mod file;
+
+use std::env;
 use std::path;
+use std::str;
+
+use serde_derive::Deserialize;
+
+use crate::data::Entry;
+use crate::matcher::MatcherOptions;
+use crate::project;
+use crate::{Error, Result};
+
+pub use self::exclude::Exclusions;
+
+/// How matching entries are ordered before being handed to the matcher.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ranking {
+    /// By accumulated weight alone, like the original autojump.
+    Weight,
+    /// By weight scaled down as the last visit gets older.
+    Frecency,
+}
+
+impl Ranking {
+    pub fn score(&self, entry: &Entry, now: u64) -> f64 {
+        match *self {
+            Ranking::Weight => entry.weight,
+            Ranking::Frecency => entry.frecency(now),
+        }
+    }
+}
+
+impl str::FromStr for Ranking {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Ranking, ()> {
+        match s {
+            "weight" => Ok(Ranking::Weight),
+            "frecency" => Ok(Ranking::Frecency),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Limits that trigger scaling down of all weights when the database is
+/// saved, so that stale entries eventually fall out of it.
+#[derive(Clone, Debug)]
+pub struct Aging {
+    /// Age the database when the sum of all weights exceeds this.
+    pub max_total_weight: Option<f64>,
+    /// Age the database when it holds more entries than this.
+    pub max_entries: Option<usize>,
+    /// Fraction of the limit the total weight is scaled down to.
+    pub factor: f64,
+    /// Entries whose weight drops below this after aging are removed.
+    pub min_weight: f64,
+}
+
+impl Aging {
+    pub fn defaults() -> Aging {
+        // Disabled unless asked for, to stay a drop-in replacement.
+        Aging {
+            max_total_weight: None,
+            max_entries: None,
+            factor: 0.9,
+            min_weight: 1.0,
+        }
+    }
+}
+
+/// On-disk format of the data file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// The tab-separated text file of the original autojump.
+    Text,
+    /// A compact binary file, faster to load for large databases.
+    Binary,
+}
+
+impl str::FromStr for Backend {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Backend, ()> {
+        match s {
+            "text" => Ok(Backend::Text),
+            "binary" => Ok(Backend::Binary),
+            _ => Err(()),
+        }
+    }
+}
 
 pub struct Config {
     pub prefix: path::PathBuf,
     pub data_path: path::PathBuf,
     pub backup_path: path::PathBuf,
+    pub lock_path: path::PathBuf,
+    pub journal_path: path::PathBuf,
+    /// Optional TOML file overriding the tunables below.
+    pub config_path: path::PathBuf,
+    pub increase_weight: isize,
+    pub decrease_weight: isize,
+    /// Minimum age of the backup file in seconds before it is refreshed.
+    pub backup_threshold: u64,
+    /// Maximum number of entries offered for tab completion.
+    pub completion_count: usize,
+    /// How long to wait for another process to release the data file, in
+    /// milliseconds.
+    pub lock_timeout: u64,
+    /// Whether `--add` appends to the journal instead of rewriting the data
+    /// file.
+    pub journal: bool,
+    /// Journal size in bytes beyond which it is merged into the data file.
+    pub journal_max_size: u64,
+    pub backend: Backend,
+    pub ranking: Ranking,
+    pub aging: Aging,
+    pub matcher: MatcherOptions,
+    /// Directories that are never recorded.
+    pub exclude: Exclusions,
+    /// Names of files or directories whose presence marks a project root.
+    pub project_markers: Vec<String>,
+    /// Program and leading arguments that `--open` runs with the directory
+    /// appended.
+    pub opener: Vec<String>,
+}
+
+/// Splits a command line like `code --new-window` into the program and its
+/// arguments. Returns `None` if there is no program.
+pub fn parse_command(s: &str) -> Option<Vec<String>> {
+    let command: Vec<_> = s.split_whitespace().map(|s| s.to_string()).collect();
+    if command.is_empty() {
+        None
+    } else {
+        Some(command)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn default_opener() -> Vec<String> {
+    vec!["xdg-open".to_string()]
+}
+
+#[cfg(target_os = "macos")]
+fn default_opener() -> Vec<String> {
+    vec!["open".to_string()]
+}
+
+#[cfg(windows)]
+fn default_opener() -> Vec<String> {
+    // Not `cmd /C start`, which would interpret `&`, `^` and the like in the
+    // directory name.
+    vec!["explorer.exe".to_string()]
+}
+
+/// Returns the value of the environment variable `key`, if set, failing on
+/// values that don't parse like the config file would.
+fn env_parse<T>(key: &str) -> Result<Option<T>>
+where
+    T: str::FromStr,
+{
+    match env::var(key) {
+        Ok(s) => match s.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => Err(Error::Env(
+                key.to_string(),
+                format!("invalid value '{}'", s),
+            )),
+        },
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => {
+            Err(Error::Env(key.to_string(), "not valid Unicode".to_string()))
+        }
+    }
 }
 
 #[cfg(unix)]
@@ -28,7 +199,6 @@ pub fn xdg_home_hardcoded() -> path::PathBuf {
 // TODO: is this cfg appropriate for *all* Unix platforms, especially BSD?
 #[cfg(all(unix, not(target_os = "macos")))]
 fn data_home() -> path::PathBuf {
-    use std::env;
     // Use $XDG_DATA_HOME if defined, ~/.local/share/autojump otherwise.
     if let Some(home_s) = env::var_os("XDG_DATA_HOME") {
         let mut tmp = path::PathBuf::from(home_s);
@@ -49,7 +219,6 @@ fn data_home() -> path::PathBuf {
 
 #[cfg(windows)]
 fn data_home() -> path::PathBuf {
-    use std::env;
     // `%APPDATA%` is always present on Windows, unless someone actually
     // decided to remove it in Control Panel. We wouldn't want to support
     // those people indeed...
@@ -59,20 +228,203 @@ fn data_home() -> path::PathBuf {
 }
 
 impl Config {
+    /// Returns the built-in configuration, without consulting the config
+    /// file or the environment.
     pub fn defaults() -> Config {
         let data_home = data_home();
         Config::from_prefix(&data_home)
     }
 
+    /// Returns the configuration in effect: the built-in defaults, overridden
+    /// by the config file if it exists, then by `AUTOJUMP_*` environment
+    /// variables.
+    pub fn load() -> Result<Config> {
+        let mut config = Config::defaults();
+
+        if let Some(p) = env::var_os("AUTOJUMP_CONFIG") {
+            config.config_path = path::PathBuf::from(p);
+        }
+        if config.config_path.exists() {
+            file::load(&mut config)?;
+        }
+
+        config.apply_env()?;
+        Ok(config)
+    }
+
     pub fn from_prefix(data_home: &path::Path) -> Config {
         let data_home = data_home.to_path_buf();
         let data_path = data_home.join("autojump.txt");
         let backup_path = data_home.join("autojump.txt.bak");
+        let lock_path = data_home.join("autojump.txt.lock");
+        let journal_path = data_home.join("autojump.txt.journal");
+        let config_path = data_home.join("config.toml");
 
         Config {
             prefix: data_home,
             data_path,
             backup_path,
+            lock_path,
+            journal_path,
+            config_path,
+            increase_weight: 10,
+            decrease_weight: 15,
+            backup_threshold: 24 * 60 * 60, // 1 d
+            completion_count: 9,
+            lock_timeout: 1000,
+            journal: false,
+            journal_max_size: 64 * 1024,
+            backend: Backend::Text,
+            ranking: Ranking::Weight,
+            aging: Aging::defaults(),
+            matcher: MatcherOptions::defaults(),
+            exclude: Exclusions::empty(),
+            project_markers: project::DEFAULT_MARKERS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            opener: default_opener(),
+        }
+    }
+
+    /// Switches to another data file format, which lives in a file of its
+    /// own.
+    pub fn set_backend(&mut self, backend: Backend) {
+        let name = match backend {
+            Backend::Text => "autojump.txt",
+            Backend::Binary => "autojump.db",
+        };
+
+        self.backend = backend;
+        self.data_path = self.prefix.join(name);
+        self.backup_path = self.prefix.join(format!("{}.bak", name));
+    }
+
+    /// Returns whether `p` is never to be recorded, by any means of adding
+    /// entries: `$HOME`, like the original, and `exclude`.
+    pub fn is_ignored(&self, p: &path::Path) -> bool {
+        if dirs::home_dir().is_some_and(|home| p == home) {
+            return true;
+        }
+
+        self.exclude.matches(p)
+    }
+
+    fn apply_env(&mut self) -> Result<()> {
+        if let Some(w) = env_parse("AUTOJUMP_INCREASE_WEIGHT")? {
+            self.increase_weight = w;
+        }
+        if let Some(w) = env_parse("AUTOJUMP_DECREASE_WEIGHT")? {
+            self.decrease_weight = w;
+        }
+        if let Some(t) = env_parse("AUTOJUMP_BACKUP_THRESHOLD")? {
+            self.backup_threshold = t;
+        }
+        if let Some(n) = env_parse("AUTOJUMP_COMPLETION_COUNT")? {
+            self.completion_count = n;
+        }
+        if let Some(t) = env_parse("AUTOJUMP_LOCK_TIMEOUT")? {
+            self.lock_timeout = t;
+        }
+        if let Some(b) = env_parse("AUTOJUMP_JOURNAL")? {
+            self.journal = b;
+        }
+        if let Some(n) = env_parse("AUTOJUMP_JOURNAL_MAX_SIZE")? {
+            self.journal_max_size = n;
+        }
+        if let Some(t) = env_parse("AUTOJUMP_FUZZY_THRESHOLD")? {
+            self.matcher.fuzzy_threshold = t;
+        }
+        if let Some(algorithm) = env_parse("AUTOJUMP_FUZZY_ALGORITHM")? {
+            self.matcher.fuzzy_algorithm = algorithm;
+        }
+        if let Some(mode) = env_parse("AUTOJUMP_MATCH_MODE")? {
+            self.matcher.mode = mode;
+        }
+        if let Some(b) = env_parse("AUTOJUMP_FOLD_ACCENTS")? {
+            self.matcher.fold_accents = b;
+        }
+        if let Some(ranking) = env_parse("AUTOJUMP_RANKING")? {
+            self.ranking = ranking;
+        }
+        if let Some(backend) = env_parse("AUTOJUMP_BACKEND")? {
+            self.set_backend(backend);
+        }
+        if let Some(w) = env_parse("AUTOJUMP_MAX_TOTAL_WEIGHT")? {
+            self.aging.max_total_weight = Some(w);
+        }
+        if let Some(n) = env_parse("AUTOJUMP_MAX_ENTRIES")? {
+            self.aging.max_entries = Some(n);
+        }
+        // A list of patterns separated like $PATH.
+        if let Some(s) = env::var_os("AUTOJUMP_EXCLUDE") {
+            let patterns: Vec<_> = env::split_paths(&s)
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+            self.exclude = Exclusions::from_patterns(&patterns)
+                .map_err(|e| Error::Env("AUTOJUMP_EXCLUDE".to_string(), e.to_string()))?;
+        }
+        if let Some(command) = env_parse::<String>("AUTOJUMP_OPENER")?
+            .as_deref()
+            .and_then(parse_command)
+        {
+            self.opener = command;
+        }
+        if let Some(s) = env::var_os("AUTOJUMP_PROJECT_MARKERS") {
+            self.project_markers = env::split_paths(&s)
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_is_ignored() {
+        let mut config = Config::from_prefix(path::Path::new("/nonexistent"));
+        let home = dirs::home_dir().unwrap();
+        assert!(config.is_ignored(&home));
+        assert!(!config.is_ignored(&home.join("foo")));
+        assert!(!config.is_ignored(path::Path::new("/tmp/foo")));
+
+        config.exclude = Exclusions::from_patterns(&["/tmp/*", "/mnt/nfs"]).unwrap();
+        assert!(config.is_ignored(&home));
+        assert!(config.is_ignored(path::Path::new("/tmp/foo")));
+        assert!(config.is_ignored(path::Path::new("/mnt/nfs/share")));
+        assert!(!config.is_ignored(path::Path::new("/tmp")));
+        assert!(!config.is_ignored(&home.join("foo")));
+    }
+
+    #[test]
+    fn test_apply_env() {
+        let mut config = Config::from_prefix(path::Path::new("/nonexistent"));
+        env::set_var("AUTOJUMP_INCREASE_WEIGHT", "20");
+        env::set_var("AUTOJUMP_MATCH_MODE", "scored");
+        let result = config.apply_env();
+        env::remove_var("AUTOJUMP_INCREASE_WEIGHT");
+        env::remove_var("AUTOJUMP_MATCH_MODE");
+        result.unwrap();
+        assert_eq!(config.increase_weight, 20);
+        assert_eq!(config.matcher.mode, crate::matcher::MatchMode::Scored);
+
+        for &(key, value) in &[
+            ("AUTOJUMP_JOURNAL", "yes"),
+            ("AUTOJUMP_MATCH_MODE", "fuzzy"),
+            ("AUTOJUMP_EXCLUDE", "/foo/[bar"),
+        ] {
+            env::set_var(key, value);
+            let result = config.apply_env();
+            env::remove_var(key);
+            match result {
+                Err(Error::Env(k, _)) => assert_eq!(k, key),
+                _ => panic!("{}={} was accepted", key, value),
+            }
         }
     }
 }
@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+
+use super::entry::Entry;
+use crate::Aging;
+
+fn total_weight(data: &[Entry]) -> f64 {
+    data.iter().map(|ent| ent.weight).sum()
+}
+
+fn need_aging(aging: &Aging, data: &[Entry]) -> bool {
+    if let Some(max) = aging.max_entries {
+        if data.len() > max {
+            return true;
+        }
+    }
+
+    if let Some(max) = aging.max_total_weight {
+        if total_weight(data) > max {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Scales down the weights of all entries if any limit in `aging` is
+/// exceeded, dropping those that become negligible. Otherwise the data is
+/// returned as-is.
+///
+/// Like in `z`, the total weight is brought down to `aging.factor` times the
+/// limit; if the entry count is still too large afterwards, the lightest
+/// entries are dropped.
+pub fn age<'a>(aging: &Aging, data: &'a [Entry]) -> Cow<'a, [Entry]> {
+    if !need_aging(aging, data) {
+        return Cow::Borrowed(data);
+    }
+
+    let total = total_weight(data);
+    let scale = match aging.max_total_weight {
+        Some(max) if total > max => aging.factor * max / total,
+        _ => aging.factor,
+    };
+
+    let mut result: Vec<_> = data
+        .iter()
+        .cloned()
+        .map(|mut ent| {
+            ent.weight *= scale;
+            ent
+        })
+        .filter(|ent| ent.weight >= aging.min_weight)
+        .collect();
+
+    if let Some(max) = aging.max_entries {
+        if result.len() > max {
+            // Heaviest first, so that truncating drops the lightest ones.
+            result.sort_by(|a, b| b.cmp(a));
+            result.truncate(max);
+        }
+    }
+
+    Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weights(data: &[Entry]) -> Vec<f64> {
+        data.iter().map(|ent| ent.weight).collect()
+    }
+
+    #[test]
+    fn test_age_disabled() {
+        let data = vec![Entry::new("/a", 1000.0), Entry::new("/b", 0.5)];
+        let aged = age(&Aging::defaults(), &data);
+        assert!(matches!(aged, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_age_total_weight() {
+        let mut aging = Aging::defaults();
+        aging.max_total_weight = Some(100.0);
+        aging.factor = 0.5;
+
+        let data = vec![Entry::new("/a", 20.0), Entry::new("/b", 40.0)];
+        assert!(matches!(age(&aging, &data), Cow::Borrowed(_)));
+
+        let data = vec![
+            Entry::new("/a", 100.0),
+            Entry::new("/b", 99.0),
+            Entry::new("/c", 1.0),
+        ];
+        let aged = age(&aging, &data);
+        // scaled to 50 in total, with "/c" falling below 1.0
+        assert_eq!(weights(&aged), [25.0, 24.75]);
+    }
+
+    #[test]
+    fn test_age_max_entries() {
+        let mut aging = Aging::defaults();
+        aging.max_entries = Some(2);
+        aging.factor = 1.0;
+
+        let data = vec![
+            Entry::new("/a", 10.0),
+            Entry::new("/b", 30.0),
+            Entry::new("/c", 20.0),
+        ];
+        let aged = age(&aging, &data);
+        let paths: Vec<_> = aged.iter().map(|ent| ent.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["/b", "/c"]);
+    }
+}
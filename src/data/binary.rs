@@ -0,0 +1,164 @@
+use std::cmp;
+use std::io;
+use std::io::{Read, Write};
+
+use super::entry::Entry;
+use super::escape::{path_from_bytes, path_to_bytes};
+use super::storage::Storage;
+
+/// A compact binary format that loads without any parsing of text.
+///
+/// All integers are little-endian:
+///
+/// ```ignore
+/// "AJDB" version:u32 count:u64
+/// { weight:f64 last_access:u64 visits:u64 path_len:u32 path:[u8; path_len] } * count
+/// ```
+pub struct BinaryStorage;
+
+const MAGIC: &[u8; 4] = b"AJDB";
+const VERSION: u32 = 1;
+/// Stands for an unknown `last_access`.
+const NO_TIMESTAMP: u64 = u64::MAX;
+
+pub(super) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(super) fn read_u32(r: &mut dyn Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(super) fn read_u64(r: &mut dyn Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(super) fn read_f64(r: &mut dyn Read) -> io::Result<f64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+fn read_entry(r: &mut dyn Read) -> io::Result<Entry> {
+    let weight = read_f64(r)?;
+    let last_access = read_u64(r)?;
+    let visits = read_u64(r)?;
+    let path_len = read_u32(r)?;
+
+    // The buffer only grows as far as the data actually goes, so a corrupt
+    // length can't have us allocate gigabytes up front.
+    let mut path_buf = vec![];
+    r.take(path_len.into()).read_to_end(&mut path_buf)?;
+    if path_buf.len() != path_len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let mut entry = Entry::new(path_from_bytes(path_buf), weight);
+    if last_access != NO_TIMESTAMP {
+        entry.last_access = Some(last_access);
+    }
+    entry.visits = visits;
+    Ok(entry)
+}
+
+impl Storage for BinaryStorage {
+    fn read(&self, r: &mut dyn Read) -> io::Result<Vec<Entry>> {
+        let mut reader = io::BufReader::new(r);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not an autojump database"));
+        }
+        if read_u32(&mut reader)? != VERSION {
+            return Err(invalid_data("unsupported database version"));
+        }
+
+        let count = read_u64(&mut reader)?;
+        // Don't trust the count blindly for the allocation.
+        let mut result = Vec::with_capacity(cmp::min(count, 65536) as usize);
+        for _ in 0..count {
+            result.push(read_entry(&mut reader)?);
+        }
+
+        Ok(result)
+    }
+
+    fn write(&self, w: &mut dyn Write, data: &[Entry]) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(w);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(data.len() as u64).to_le_bytes())?;
+
+        for entry in data.iter() {
+            let path = path_to_bytes(&entry.path);
+            let last_access = entry.last_access.unwrap_or(NO_TIMESTAMP);
+
+            writer.write_all(&entry.weight.to_le_bytes())?;
+            writer.write_all(&last_access.to_le_bytes())?;
+            writer.write_all(&entry.visits.to_le_bytes())?;
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(&path)?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut touched = Entry::new("/bar", 20.0);
+        touched.touch(1600000000);
+        let data = vec![Entry::new("/foo", 10.5), touched];
+
+        let mut buf = vec![];
+        BinaryStorage.write(&mut buf, &data).unwrap();
+        assert_eq!(&buf[..4], MAGIC);
+
+        let loaded = BinaryStorage.read(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].path, path::Path::new("/foo"));
+        assert_eq!(loaded[0].weight, 10.5);
+        assert_eq!(loaded[0].last_access, None);
+        assert_eq!(loaded[1].path, path::Path::new("/bar"));
+        assert_eq!(loaded[1].last_access, Some(1600000000));
+        assert_eq!(loaded[1].visits, 1);
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        assert!(BinaryStorage.read(&mut &b""[..]).is_err());
+        assert!(BinaryStorage.read(&mut &b"10\t/foo\n"[..]).is_err());
+
+        // truncated in the middle of an entry
+        let mut buf = vec![];
+        BinaryStorage
+            .write(&mut buf, &[Entry::new("/foo", 1.0)])
+            .unwrap();
+        buf.truncate(buf.len() - 1);
+        assert!(BinaryStorage.read(&mut buf.as_slice()).is_err());
+
+        // a corrupt path length, way past the end of the data
+        let mut buf = vec![];
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&VERSION.to_le_bytes());
+        buf.extend_from_slice(&1u64.to_le_bytes());
+        buf.extend_from_slice(&1.0f64.to_le_bytes());
+        buf.extend_from_slice(&NO_TIMESTAMP.to_le_bytes());
+        buf.extend_from_slice(&0u64.to_le_bytes());
+        buf.extend_from_slice(&u32::MAX.to_le_bytes());
+        buf.extend_from_slice(b"/foo");
+        let e = BinaryStorage.read(&mut buf.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,15 +1,15 @@
 use std::fs;
 use std::io;
-use std::io::{BufRead, Write};
 use std::path;
 use std::time;
 
 use atomicwrites;
 
+use super::aging::age;
 use super::entry::Entry;
-use crate::Config;
-
-const BACKUP_THRESHOLD: u64 = 24 * 60 * 60; // 1 d
+use super::journal::{remove_journal, replay_journal};
+use super::lock::Lock;
+use crate::{Backend, Config, Error, Result};
 
 #[cfg(target_os = "macos")]
 fn migrate_osx_xdg_data(config: &Config) -> io::Result<()> {
@@ -33,71 +33,58 @@ fn migrate_osx_xdg_data(_: &Config) -> io::Result<()> {
     Ok(())
 }
 
-fn load_line(line: &str) -> Option<Entry> {
-    let parts: Vec<_> = line.splitn(2, '\t').collect();
-    if parts.len() != 2 {
-        return None;
-    }
-
-    let weight = parts[0].parse::<f64>().ok()?;
-    let path = path::PathBuf::from(parts[1]);
-    Some(Entry::new(path, weight))
+fn load_from_file(config: &Config, mut f: fs::File) -> io::Result<Vec<Entry>> {
+    config.storage().read(&mut f)
 }
 
-fn load_from_file(f: fs::File) -> io::Result<Vec<Entry>> {
-    let reader = io::BufReader::new(f);
-    let mut result = vec![];
-
-    for line in reader.lines() {
-        let line = line?;
-        if let Some(entry) = load_line(&line) {
-            result.push(entry)
-        }
-    }
-
-    Ok(result)
+fn open(p: &path::Path) -> Result<fs::File> {
+    fs::File::open(p).map_err(|e| Error::file(p, e))
 }
 
-pub fn load(config: &Config) -> Vec<Entry> {
+/// Loads the data file, with any pending journal records applied.
+pub fn load(config: &Config) -> Result<Vec<Entry>> {
     // Only necessary when running on macOS, no-op on others
-    migrate_osx_xdg_data(config).unwrap();
+    migrate_osx_xdg_data(config)?;
 
+    let mut data = load_data_file(config)?;
+    replay_journal(config, &mut data)?;
+    Ok(data)
+}
+
+fn load_data_file(config: &Config) -> Result<Vec<Entry>> {
     if !config.data_path.exists() {
-        return vec![];
+        return load_legacy_text(config);
     }
 
-    let result = load_from_file(fs::File::open(&config.data_path).unwrap());
-    if let Ok(result) = result {
-        result
-    } else {
-        load_backup(config)
+    match load_from_file(config, open(&config.data_path)?) {
+        Ok(result) => Ok(result),
+        Err(_) if config.backup_path.exists() => load_backup(config),
+        // Loading nothing would have the next save wipe the data file.
+        Err(e) => Err(Error::file(&config.data_path, e)),
     }
 }
 
-fn load_backup(config: &Config) -> Vec<Entry> {
-    if config.backup_path.exists() {
-        fs::rename(&config.backup_path, &config.data_path).unwrap();
-        load_from_file(fs::File::open(&config.data_path).unwrap()).unwrap()
-    } else {
-        vec![]
+/// Picks up the text data file when switching to another backend for the
+/// first time. It is left in place; the next save creates the new file.
+fn load_legacy_text(config: &Config) -> Result<Vec<Entry>> {
+    let text_path = Config::from_prefix(&config.prefix).data_path;
+    if config.backend == Backend::Text || !text_path.exists() {
+        return Ok(vec![]);
     }
-}
 
-fn save_to(file: &fs::File, data: &[Entry]) -> io::Result<()> {
-    let mut writer = io::BufWriter::new(file);
-    for entry in data.iter() {
-        writeln!(
-            &mut writer,
-            "{}\t{}",
-            entry.weight,
-            entry.path.to_string_lossy()
-        )?;
-    }
+    Backend::Text
+        .storage()
+        .read(&mut open(&text_path)?)
+        .map_err(|e| Error::file(&text_path, e))
+}
 
-    Ok(())
+fn load_backup(config: &Config) -> Result<Vec<Entry>> {
+    fs::rename(&config.backup_path, &config.data_path)
+        .map_err(|e| Error::file(&config.backup_path, e))?;
+    load_from_file(config, open(&config.data_path)?).map_err(|e| Error::file(&config.data_path, e))
 }
 
-fn maybe_create_data_dir(config: &Config) -> io::Result<()> {
+pub(super) fn maybe_create_data_dir(config: &Config) -> io::Result<()> {
     if !config.prefix.exists() {
         fs::create_dir_all(&config.prefix)
     } else {
@@ -113,7 +100,7 @@ fn need_backup(config: &Config) -> io::Result<bool> {
         let mtime = metadata.modified()?;
 
         match now.duration_since(mtime) {
-            Ok(duration) => Ok(duration.as_secs() > BACKUP_THRESHOLD),
+            Ok(duration) => Ok(duration.as_secs() > config.backup_threshold),
             Err(_) => {
                 // Clock skew: mtime is in the future!
                 // TODO: print warning
@@ -135,13 +122,185 @@ fn maybe_backup(config: &Config) -> io::Result<()> {
     Ok(())
 }
 
-pub fn save(config: &Config, data: &[Entry]) -> io::Result<()> {
-    maybe_create_data_dir(config)?;
+/// Writes `data` to the data file.
+///
+/// The journal is left alone, so its records still apply on top of `data`;
+/// `update` is what folds them into the data file.
+pub fn save(config: &Config, data: &[Entry]) -> Result<()> {
+    maybe_create_data_dir(config).map_err(|e| Error::file(&config.prefix, e))?;
+
+    let data = age(&config.aging, data);
 
     let af = atomicwrites::AtomicFile::new(&config.data_path, atomicwrites::AllowOverwrite);
-    af.write(|f| save_to(f, data))?;
+    af.write(|mut f| config.storage().write(&mut f, &data))
+        .map_err(|e| Error::file(&config.data_path, e.into()))?;
 
-    maybe_backup(config)?;
+    maybe_backup(config).map_err(|e| Error::file(&config.backup_path, e))?;
 
     Ok(())
 }
+
+/// Loads the data, lets `f` modify it, then saves the result, all while
+/// holding the lock so concurrent updates are not lost.
+pub fn update<F, T>(config: &Config, f: F) -> Result<T>
+where
+    F: FnOnce(&mut Vec<Entry>) -> T,
+{
+    let _lock = Lock::acquire(config)?;
+    update_locked(config, f)
+}
+
+/// `update` for callers already holding the lock.
+pub(super) fn update_locked<F, T>(config: &Config, f: F) -> Result<T>
+where
+    F: FnOnce(&mut Vec<Entry>) -> T,
+{
+    let mut data = load(config)?;
+    let result = f(&mut data);
+    save(config, &data)?;
+    // Its records are part of the data file now.
+    remove_journal(config)?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ranking;
+
+    #[test]
+    fn test_update() {
+        let config = Config::from_prefix(&super::super::test_prefix("update"));
+
+        let n = update(&config, |data| {
+            data.push(Entry::new("/foo", 10.0));
+            data.len()
+        })
+        .unwrap();
+        assert_eq!(n, 1);
+        // the lock is released again
+        drop(Lock::acquire(&config).unwrap());
+
+        let data = load(&config).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].path, path::Path::new("/foo"));
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+
+    #[test]
+    fn test_load_unreadable() {
+        let config = Config::from_prefix(&super::super::test_prefix("unreadable"));
+        // Opening a directory works, but reading from it doesn't.
+        fs::create_dir(&config.data_path).unwrap();
+
+        match load(&config) {
+            Err(Error::File(p, _)) => assert_eq!(p, config.data_path),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+
+    #[test]
+    fn test_load_corrupt() {
+        let mut config = Config::from_prefix(&super::super::test_prefix("corrupt"));
+        config.set_backend(Backend::Binary);
+        fs::write(&config.data_path, b"AJDB garbage").unwrap();
+
+        match load(&config) {
+            Err(Error::File(p, e)) => {
+                assert_eq!(p, config.data_path);
+                assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // a backup is used instead, if there is one
+        save(&config, &[Entry::new("/foo", 10.0)]).unwrap();
+        assert!(config.backup_path.exists());
+        fs::write(&config.data_path, b"AJDB garbage").unwrap();
+        assert_eq!(load(&config).unwrap().len(), 1);
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+
+    #[test]
+    fn test_save_unwritable() {
+        let prefix = super::super::test_prefix("unwritable");
+
+        // a data directory that can't be created
+        fs::write(prefix.join("file"), b"").unwrap();
+        let config = Config::from_prefix(&prefix.join("file").join("autojump"));
+        match save(&config, &[Entry::new("/foo", 10.0)]) {
+            Err(Error::File(p, _)) => assert_eq!(p, config.prefix),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // a read-only data directory
+        let config = Config::from_prefix(&prefix.join("ro"));
+        fs::create_dir(&config.prefix).unwrap();
+        let mut permissions = config.prefix.metadata().unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&config.prefix, permissions.clone()).unwrap();
+        // Privileged users may write there regardless.
+        if fs::File::create(config.prefix.join("probe")).is_err() {
+            match save(&config, &[Entry::new("/foo", 10.0)]) {
+                Err(Error::File(p, _)) => assert_eq!(p, config.data_path),
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&config.prefix, permissions).unwrap();
+        fs::remove_dir_all(&prefix).unwrap();
+    }
+
+    #[test]
+    fn test_save_original_format() {
+        let mut config = Config::from_prefix(&super::super::test_prefix("original-format"));
+        let mut entry = Entry::new("/foo", 10.0);
+        entry.touch(1600000000);
+
+        // lines the original autojump takes, i.e. of exactly two columns
+        save(&config, &[entry.clone()]).unwrap();
+        let text = fs::read_to_string(&config.data_path).unwrap();
+        assert!(
+            text.lines().all(|l| l.split('\t').count() == 2),
+            "{:?}",
+            text
+        );
+        let data = load(&config).unwrap();
+        assert_eq!(data[0].path, entry.path);
+        assert_eq!(data[0].weight, entry.weight);
+
+        // the metadata is only kept for frecency ranking
+        config.ranking = Ranking::Frecency;
+        save(&config, &[entry]).unwrap();
+        let data = load(&config).unwrap();
+        assert_eq!(data[0].last_access, Some(1600000000));
+        assert_eq!(data[0].visits, 1);
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_to_binary() {
+        let text_config = Config::from_prefix(&super::super::test_prefix("migrate"));
+        save(&text_config, &[Entry::new("/foo", 10.0)]).unwrap();
+
+        let mut config = Config::from_prefix(&text_config.prefix);
+        config.set_backend(Backend::Binary);
+        assert_ne!(config.data_path, text_config.data_path);
+
+        let data = load(&config).unwrap();
+        assert_eq!(data.len(), 1);
+        save(&config, &data).unwrap();
+        let bytes = fs::read(&config.data_path).unwrap();
+        assert_eq!(&bytes[..4], b"AJDB");
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+}
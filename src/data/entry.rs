@@ -1,11 +1,29 @@
 use std::cmp;
 use std::fmt;
 use std::path;
+use std::time;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
 
 #[derive(Clone, Debug)]
 pub struct Entry {
     pub path: path::PathBuf,
     pub weight: f64,
+    /// Time of the last visit in seconds since the Unix epoch, if known.
+    pub last_access: Option<u64>,
+    /// Number of visits recorded since the entry started being tracked.
+    pub visits: u64,
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    match time::SystemTime::now().duration_since(time::UNIX_EPOCH) {
+        Ok(d) => d.as_secs(),
+        // Clock is before 1970, treat everything as infinitely old.
+        Err(_) => 0,
+    }
 }
 
 impl Entry {
@@ -16,8 +34,44 @@ impl Entry {
         Entry {
             path: path.into(),
             weight,
+            last_access: None,
+            visits: 0,
         }
     }
+
+    /// Records a visit happening at `timestamp`. A visit older than the last
+    /// one known, e.g. from an imported database, doesn't move it back.
+    pub fn touch(&mut self, timestamp: u64) {
+        self.last_access = Some(
+            self.last_access
+                .map_or(timestamp, |t| cmp::max(t, timestamp)),
+        );
+        self.visits += 1;
+    }
+
+    /// Returns the weight of the entry scaled by how recently it is visited,
+    /// in the spirit of `z`'s frecency buckets.
+    ///
+    /// Entries without a known access time are treated as if they were last
+    /// visited long ago.
+    pub fn frecency(&self, now: u64) -> f64 {
+        let factor = match self.last_access {
+            Some(t) => {
+                let age = now.saturating_sub(t);
+                if age < HOUR {
+                    4.0
+                } else if age < DAY {
+                    2.0
+                } else if age < WEEK {
+                    0.5
+                } else {
+                    0.25
+                }
+            }
+            None => 0.25,
+        };
+        self.weight * factor
+    }
 }
 
 impl fmt::Display for Entry {
@@ -34,13 +88,13 @@ impl AsRef<path::Path> for Entry {
 
 impl PartialOrd for Entry {
     fn partial_cmp(&self, other: &Entry) -> Option<cmp::Ordering> {
-        self.weight.partial_cmp(&other.weight)
+        Some(self.cmp(other))
     }
 }
 
 impl PartialEq for Entry {
     fn eq(&self, other: &Entry) -> bool {
-        self.weight == other.weight
+        self.cmp(other) == cmp::Ordering::Equal
     }
 }
 
@@ -48,7 +102,47 @@ impl Eq for Entry {}
 
 impl Ord for Entry {
     fn cmp(&self, other: &Entry) -> cmp::Ordering {
-        // We know that NaN's don't exist in our use case, so just unwrap it.
-        self.partial_cmp(other).unwrap()
+        // A total order, so that a NaN sneaking in can't make sorting panic.
+        self.weight.total_cmp(&other.weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frecency() {
+        let now = 1_000_000_000;
+        let mut entry = Entry::new("/foo", 10.0);
+        assert_eq!(entry.frecency(now), 2.5);
+
+        entry.touch(now - 10);
+        assert_eq!(entry.visits, 1);
+        assert_eq!(entry.frecency(now), 40.0);
+
+        entry.touch(now - 3 * WEEK);
+        assert_eq!(entry.visits, 2);
+        assert_eq!(entry.last_access, Some(now - 10));
+
+        entry.last_access = Some(now - 2 * HOUR);
+        assert_eq!(entry.frecency(now), 20.0);
+        entry.last_access = Some(now - 2 * DAY);
+        assert_eq!(entry.frecency(now), 5.0);
+        entry.last_access = Some(now - 2 * WEEK);
+        assert_eq!(entry.frecency(now), 2.5);
+    }
+
+    #[test]
+    fn test_cmp_nan() {
+        let mut entries = [
+            Entry::new("/foo", 1.0),
+            Entry::new("/bar", f64::NAN),
+            Entry::new("/baz", 2.0),
+        ];
+        entries.sort();
+        assert_eq!(entries[0].weight, 1.0);
+        assert_eq!(entries[1].weight, 2.0);
+        assert!(entries[2].weight.is_nan());
     }
 }
@@ -0,0 +1,169 @@
+//! Conversions between paths and the text stored in data files.
+//!
+//! On Unix a path is an arbitrary byte string, which can't always be written
+//! out as UTF-8. Such bytes are stored as `\xHH` escapes instead, along with
+//! line breaks which would otherwise break up the line. A backslash is only
+//! escaped when it happens to be followed by something looking like an escape,
+//! so most paths read the same either way.
+//!
+//! That still leaves paths like `/srv/a\x41`, which the original autojump
+//! and older versions wrote as is, so data files holding escaped paths are
+//! marked as such; see `text`.
+
+use std::borrow::Cow;
+use std::path;
+
+#[cfg(unix)]
+pub(super) fn path_to_bytes(p: &path::Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(p.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub(super) fn path_to_bytes(p: &path::Path) -> Cow<'_, [u8]> {
+    match p.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+#[cfg(unix)]
+pub(super) fn path_from_bytes(b: Vec<u8>) -> path::PathBuf {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+    path::PathBuf::from(OsString::from_vec(b))
+}
+
+#[cfg(not(unix))]
+pub(super) fn path_from_bytes(b: Vec<u8>) -> path::PathBuf {
+    path::PathBuf::from(String::from_utf8_lossy(&b).into_owned())
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Returns the byte encoded by an escape at the start of `s`, if any.
+fn parse_escape(s: &[u8]) -> Option<u8> {
+    if s.len() < 4 || s[0] != b'\\' || s[1] != b'x' {
+        return None;
+    }
+    Some(hex_value(s[2])? << 4 | hex_value(s[3])?)
+}
+
+fn push_escape(result: &mut String, b: u8) {
+    result.push_str(&format!(r"\x{:02x}", b));
+}
+
+/// Renders `p` as a single line of valid UTF-8 that `decode_path` turns back
+/// into the very same path.
+#[cfg(unix)]
+pub fn encode_path(p: &path::Path) -> Cow<'_, str> {
+    let bytes = path_to_bytes(p).into_owned();
+    let mut result = String::with_capacity(bytes.len());
+
+    for chunk in bytes.utf8_chunks() {
+        let valid = chunk.valid();
+        for (i, ch) in valid.char_indices() {
+            match ch {
+                '\n' | '\r' => push_escape(&mut result, ch as u8),
+                '\\' if parse_escape(&valid.as_bytes()[i..]).is_some() => {
+                    push_escape(&mut result, b'\\')
+                }
+                _ => result.push(ch),
+            }
+        }
+        for &b in chunk.invalid() {
+            push_escape(&mut result, b);
+        }
+    }
+
+    match p.to_str() {
+        Some(s) if s == result => Cow::Borrowed(s),
+        _ => Cow::Owned(result),
+    }
+}
+
+/// Paths are always Unicode on other platforms, and backslashes are their
+/// separators, so nothing is escaped there.
+#[cfg(not(unix))]
+pub fn encode_path(p: &path::Path) -> Cow<'_, str> {
+    p.to_string_lossy()
+}
+
+/// The inverse of `encode_path`; also accepts raw bytes as found in data
+/// files written by other tools.
+#[cfg(unix)]
+pub fn decode_path(s: &[u8]) -> path::PathBuf {
+    let mut result = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if let Some(b) = parse_escape(&s[i..]) {
+            result.push(b);
+            i += 4;
+        } else {
+            result.push(s[i]);
+            i += 1;
+        }
+    }
+    path_from_bytes(result)
+}
+
+#[cfg(not(unix))]
+pub fn decode_path(s: &[u8]) -> path::PathBuf {
+    path_from_bytes(s.to_vec())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    fn p(b: &[u8]) -> &path::Path {
+        path::Path::new(OsStr::from_bytes(b))
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path(p(b"/foo/bar")), "/foo/bar");
+        assert_eq!(encode_path(p("/测试".as_bytes())), "/测试");
+        assert_eq!(encode_path(p(b"/foo\\bar")), r"/foo\bar");
+        assert_eq!(encode_path(p(b"/foo\xff\xfebar")), r"/foo\xff\xfebar");
+        assert_eq!(encode_path(p(b"/foo\nbar\r")), r"/foo\x0abar\x0d");
+        assert_eq!(encode_path(p(b"/foo\\x41")), r"/foo\x5cx41");
+        assert_eq!(encode_path(p(b"/foo\\xzz")), r"/foo\xzz");
+    }
+
+    #[test]
+    fn test_decode_path() {
+        assert_eq!(decode_path(b"/foo/bar"), p(b"/foo/bar"));
+        assert_eq!(decode_path(br"/foo\bar"), p(b"/foo\\bar"));
+        assert_eq!(decode_path(br"/foo\xff\xFEbar"), p(b"/foo\xff\xfebar"));
+        assert_eq!(decode_path(br"/foo\x5cx41"), p(b"/foo\\x41"));
+        assert_eq!(decode_path(br"/foo\x4"), p(b"/foo\\x4"));
+        // raw bytes are passed through
+        assert_eq!(decode_path(b"/foo\xff"), p(b"/foo\xff"));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let paths: &[&[u8]] = &[
+            b"/",
+            b"/foo\\",
+            b"/foo\\\\x41\\x",
+            b"/\xc3\x28/\xa0\xa1/\xe2\x82",
+            b"/\x00\x01\t\x7f",
+        ];
+        for &b in paths {
+            let encoded = encode_path(p(b));
+            assert!(!encoded.contains('\n'));
+            assert_eq!(decode_path(encoded.as_bytes()), p(b));
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Readers for the databases of other directory jumpers.
+
+use std::cmp;
+use std::io;
+use std::io::Read;
+use std::str;
+
+use super::binary::{invalid_data, read_f64, read_u32, read_u64};
+use super::entry::Entry;
+use super::escape::path_from_bytes;
+use super::text::{parse_column, read_line};
+use crate::Backend;
+
+/// A database format that can be imported.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImportFormat {
+    /// `~/.z` of z.sh: `path|rank|time` lines.
+    Z,
+    /// `db.zo` of zoxide, a bincode-encoded list of directories.
+    Zoxide,
+    /// `~/.fasd`, in the same layout as z.
+    Fasd,
+    /// The text data file of the original autojump.
+    Autojump,
+}
+
+impl str::FromStr for ImportFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<ImportFormat, ()> {
+        match s {
+            "z" => Ok(ImportFormat::Z),
+            "zoxide" => Ok(ImportFormat::Zoxide),
+            "fasd" => Ok(ImportFormat::Fasd),
+            "autojump" => Ok(ImportFormat::Autojump),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Reads a foreign database, turning it into entries whose weights are on
+/// the same scale as ours.
+///
+/// z, fasd and zoxide all add 1 to the rank on every visit, while each visit
+/// adds `increase_weight` in quadrature here, so `n` visits amount to a
+/// weight of `increase_weight * sqrt(n)`.
+pub fn read_import(
+    format: ImportFormat,
+    r: &mut dyn Read,
+    increase_weight: f64,
+) -> io::Result<Vec<Entry>> {
+    let mut result = match format {
+        ImportFormat::Z | ImportFormat::Fasd => read_z(r)?,
+        ImportFormat::Zoxide => read_zoxide(r)?,
+        ImportFormat::Autojump => return Backend::Text.storage().read(r),
+    };
+
+    for entry in result.iter_mut() {
+        entry.weight = increase_weight * entry.weight.max(0.0).sqrt();
+    }
+    Ok(result)
+}
+
+/// Parses one `path|rank|time` line. The path itself may contain `|`.
+fn load_z_line(line: &[u8]) -> Option<Entry> {
+    let mut parts = line.rsplitn(3, |&b| b == b'|');
+    let time = parse_column::<u64>(parts.next()?)?;
+    let rank = parse_column::<f64>(parts.next()?)?;
+    let path = parts.next()?;
+    if path.is_empty() {
+        return None;
+    }
+
+    let mut entry = Entry::new(path_from_bytes(path.to_vec()), rank);
+    entry.last_access = Some(time);
+    Some(entry)
+}
+
+fn read_z(r: &mut dyn Read) -> io::Result<Vec<Entry>> {
+    let mut reader = io::BufReader::new(r);
+    let mut result = vec![];
+
+    let mut buf = vec![];
+    while read_line(&mut reader, &mut buf)? {
+        // z itself silently drops malformed lines too
+        if let Some(entry) = load_z_line(&buf) {
+            result.push(entry);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Only version 3, used since zoxide 0.8, is understood.
+const ZOXIDE_VERSION: u32 = 3;
+
+/// Reads a `db.zo`, laid out as in bincode's defaults, all little-endian:
+///
+/// ```ignore
+/// version:u32 count:u64
+/// { path_len:u64 path:[u8; path_len] rank:f64 last_accessed:u64 } * count
+/// ```
+fn read_zoxide(r: &mut dyn Read) -> io::Result<Vec<Entry>> {
+    let mut reader = io::BufReader::new(r);
+
+    if read_u32(&mut reader)? != ZOXIDE_VERSION {
+        return Err(invalid_data("unsupported zoxide database version"));
+    }
+
+    let count = read_u64(&mut reader)?;
+    let mut result = Vec::with_capacity(cmp::min(count, 65536) as usize);
+    for _ in 0..count {
+        let path_len = read_u64(&mut reader)?;
+        let mut path_buf = vec![];
+        (&mut reader).take(path_len).read_to_end(&mut path_buf)?;
+        if path_buf.len() as u64 != path_len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let rank = read_f64(&mut reader)?;
+        let last_accessed = read_u64(&mut reader)?;
+
+        let mut entry = Entry::new(path_from_bytes(path_buf), rank);
+        entry.last_access = Some(last_accessed);
+        result.push(entry);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path;
+
+    #[test]
+    fn test_read_z() {
+        let input = b"/foo|4|1600000000\n/a|b|1|1600000100\nbogus\n|1|2\n";
+        let data = read_import(ImportFormat::Z, &mut &input[..], 10.0).unwrap();
+
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].path, path::Path::new("/foo"));
+        assert_eq!(data[0].weight, 20.0);
+        assert_eq!(data[0].last_access, Some(1600000000));
+        assert_eq!(data[1].path, path::Path::new("/a|b"));
+        assert_eq!(data[1].weight, 10.0);
+    }
+
+    #[test]
+    fn test_read_zoxide() {
+        let mut input = vec![];
+        input.extend_from_slice(&3u32.to_le_bytes());
+        input.extend_from_slice(&1u64.to_le_bytes());
+        input.extend_from_slice(&4u64.to_le_bytes());
+        input.extend_from_slice(b"/foo");
+        input.extend_from_slice(&9.0f64.to_le_bytes());
+        input.extend_from_slice(&1600000000u64.to_le_bytes());
+
+        let data = read_import(ImportFormat::Zoxide, &mut input.as_slice(), 10.0).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].path, path::Path::new("/foo"));
+        assert_eq!(data[0].weight, 30.0);
+        assert_eq!(data[0].last_access, Some(1600000000));
+
+        // truncated path
+        input.truncate(20);
+        assert!(read_import(ImportFormat::Zoxide, &mut input.as_slice(), 10.0).is_err());
+
+        let mut old = vec![];
+        old.extend_from_slice(&2u32.to_le_bytes());
+        assert!(read_import(ImportFormat::Zoxide, &mut old.as_slice(), 10.0).is_err());
+    }
+
+    #[test]
+    fn test_read_autojump() {
+        let input = b"12.5\t/foo\n";
+        let data = read_import(ImportFormat::Autojump, &mut &input[..], 10.0).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].weight, 12.5);
+    }
+}
@@ -0,0 +1,199 @@
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path;
+
+use super::datafile;
+use super::entry::{now, Entry};
+use super::escape::{decode_path, encode_path};
+use super::lock::Lock;
+use super::text::{parse_column, read_line};
+use super::weight::increase;
+use crate::{Config, Error, Result};
+
+/// A pending weight increase recorded in the journal.
+struct Record {
+    timestamp: u64,
+    weight: f64,
+    path: path::PathBuf,
+}
+
+/// Journal lines look like this:
+///
+/// ```ignore
+/// timestamp\tweight\tpath
+/// ```
+fn parse_record(line: &[u8]) -> Option<Record> {
+    let parts: Vec<_> = line.splitn(3, |&b| b == b'\t').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    Some(Record {
+        timestamp: parse_column(parts[0])?,
+        weight: parse_column(parts[1]).filter(|w: &f64| w.is_finite())?,
+        path: decode_path(parts[2]),
+    })
+}
+
+fn journal_size(config: &Config) -> u64 {
+    match config.journal_path.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Records an increase of the weight of `p` by `w` without rewriting the
+/// data file. The journal is merged into the data file on the next save, or
+/// right away if it has grown past `config.journal_max_size`.
+pub fn append_journal<P>(config: &Config, p: P, w: f64) -> Result<()>
+where
+    P: AsRef<path::Path>,
+{
+    let _lock = Lock::acquire(config)?;
+
+    let journal_path = &config.journal_path;
+    let line = format!("{}\t{}\t{}\n", now(), w, encode_path(p.as_ref()));
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(journal_path)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .map_err(|e| Error::file(journal_path, e))?;
+
+    if journal_size(config) > config.journal_max_size {
+        // We're already holding the lock.
+        datafile::update_locked(config, |_| ())?;
+    }
+
+    Ok(())
+}
+
+/// Applies the records in the journal, if any, to `data`.
+pub(super) fn replay_journal(config: &Config, data: &mut Vec<Entry>) -> Result<()> {
+    let journal_path = &config.journal_path;
+    let f = match fs::File::open(journal_path) {
+        Ok(f) => f,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(Error::file(journal_path, e)),
+    };
+
+    let mut reader = io::BufReader::new(f);
+    let mut line = vec![];
+    while read_line(&mut reader, &mut line).map_err(|e| Error::file(journal_path, e))? {
+        // Skip anything garbled, e.g. a record cut short by a crash.
+        if let Some(record) = parse_record(&line) {
+            increase(data, record.path, record.weight, record.timestamp);
+        }
+    }
+
+    Ok(())
+}
+
+/// Discards the journal, after its records are written to the data file.
+pub(super) fn remove_journal(config: &Config) -> Result<()> {
+    match fs::remove_file(&config.journal_path) {
+        Ok(()) => Ok(()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::file(&config.journal_path, e)),
+    }
+}
+
+/// Merges the journal into the data file, if there is anything to merge.
+pub fn compact(config: &Config) -> Result<()> {
+    if !config.journal_path.exists() {
+        return Ok(());
+    }
+
+    datafile::update(config, |_| ())
+}
+
+/// Like `compact`, but only if no other process holds the lock, for
+/// commands that merely read the data: `load` replays the journal anyway.
+pub fn try_compact(config: &Config) -> Result<()> {
+    if !config.journal_path.exists() {
+        return Ok(());
+    }
+
+    match Lock::try_acquire(config)? {
+        Some(_lock) => datafile::update_locked(config, |_| ()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_record() {
+        assert!(parse_record(b"").is_none());
+        assert!(parse_record(b"1\t/foo").is_none());
+        assert!(parse_record(b"x\t10\t/foo").is_none());
+        assert!(parse_record(b"1\tnan\t/foo").is_none());
+
+        let record = parse_record(b"1600000000\t10\t/foo\tbar").unwrap();
+        assert_eq!(record.timestamp, 1600000000);
+        assert_eq!(record.weight, 10.0);
+        assert_eq!(record.path, path::Path::new("/foo\tbar"));
+    }
+
+    #[test]
+    fn test_journal() {
+        let config = Config::from_prefix(&super::super::test_prefix("journal"));
+        datafile::save(&config, &[Entry::new("/foo", 3.0)]).unwrap();
+
+        append_journal(&config, "/foo", 4.0).unwrap();
+        append_journal(&config, "/bar", 10.0).unwrap();
+        assert!(config.journal_path.exists());
+
+        let data = datafile::load(&config).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].weight, 5.0);
+        assert_eq!(data[0].visits, 1);
+        assert_eq!(data[1].weight, 10.0);
+
+        compact(&config).unwrap();
+        assert!(!config.journal_path.exists());
+        let text = fs::read_to_string(&config.data_path).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert_eq!(datafile::load(&config).unwrap()[0].weight, 5.0);
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+
+    #[test]
+    fn test_journal_kept() {
+        let config = Config::from_prefix(&super::super::test_prefix("journal-kept"));
+        append_journal(&config, "/bar", 10.0).unwrap();
+
+        // a save of data that didn't come with the journal leaves it alone
+        datafile::save(&config, &[Entry::new("/foo", 3.0)]).unwrap();
+        assert!(config.journal_path.exists());
+        assert_eq!(datafile::load(&config).unwrap().len(), 2);
+
+        // so does a compaction while someone else holds the lock
+        let lock = Lock::acquire(&config).unwrap();
+        try_compact(&config).unwrap();
+        assert!(config.journal_path.exists());
+        drop(lock);
+
+        try_compact(&config).unwrap();
+        assert!(!config.journal_path.exists());
+        assert_eq!(datafile::load(&config).unwrap().len(), 2);
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+
+    #[test]
+    fn test_journal_size_limit() {
+        let mut config = Config::from_prefix(&super::super::test_prefix("journal-size"));
+        config.journal_max_size = 0;
+
+        append_journal(&config, "/foo", 10.0).unwrap();
+        assert!(!config.journal_path.exists());
+        assert_eq!(datafile::load(&config).unwrap().len(), 1);
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+}
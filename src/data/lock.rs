@@ -0,0 +1,99 @@
+use std::fs;
+use std::path;
+use std::thread;
+use std::time;
+
+use super::datafile::maybe_create_data_dir;
+use crate::{Config, Error, Result};
+
+const RETRY_INTERVAL: time::Duration = time::Duration::from_millis(10);
+
+/// An advisory lock on the data file, released when dropped.
+///
+/// The lock is taken with the OS file locking primitives (`flock` or
+/// `LockFileEx`) on a file next to the data file. The OS releases it when
+/// the holder exits, crashed or not, so there is never a stale lock to clean
+/// up; the file itself is simply left in place.
+pub struct Lock {
+    // Unlocked when closed.
+    _file: fs::File,
+}
+
+impl Lock {
+    /// Takes the lock, waiting up to `config.lock_timeout` milliseconds for
+    /// other processes to release it.
+    pub fn acquire(config: &Config) -> Result<Lock> {
+        let timeout = time::Duration::from_millis(config.lock_timeout);
+        match Lock::wait(config, timeout)? {
+            Some(lock) => Ok(lock),
+            None => Err(Error::LockTimeout(config.lock_path.clone())),
+        }
+    }
+
+    /// Takes the lock if no other process holds it, without waiting.
+    pub fn try_acquire(config: &Config) -> Result<Option<Lock>> {
+        Lock::wait(config, time::Duration::ZERO)
+    }
+
+    fn wait(config: &Config, timeout: time::Duration) -> Result<Option<Lock>> {
+        maybe_create_data_dir(config).map_err(|e| Error::file(&config.prefix, e))?;
+
+        let p = &config.lock_path;
+        let file = open(p).map_err(|e| Error::file(p, e))?;
+        let start = time::Instant::now();
+
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Some(Lock { _file: file })),
+                Err(fs::TryLockError::WouldBlock) => {}
+                Err(fs::TryLockError::Error(e)) => return Err(Error::file(p, e)),
+            }
+
+            if start.elapsed() >= timeout {
+                return Ok(None);
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+}
+
+fn open(p: &path::Path) -> std::io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock() {
+        let mut config = Config::from_prefix(&super::super::test_prefix("lock"));
+        config.lock_timeout = 50;
+
+        let lock = Lock::acquire(&config).unwrap();
+        match Lock::acquire(&config) {
+            Err(Error::LockTimeout(p)) => assert_eq!(p, config.lock_path),
+            _ => panic!("lock acquired twice"),
+        }
+        assert!(Lock::try_acquire(&config).unwrap().is_none());
+
+        drop(lock);
+        let _lock = Lock::acquire(&config).unwrap();
+    }
+
+    #[test]
+    fn test_lock_left_behind() {
+        let mut config = Config::from_prefix(&super::super::test_prefix("lock-left"));
+        config.lock_timeout = 0;
+
+        // A lock file nobody holds a lock on doesn't get in the way, however
+        // recent it is.
+        fs::File::create(&config.lock_path).unwrap();
+        let _lock = Lock::acquire(&config).unwrap();
+    }
+}
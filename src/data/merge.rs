@@ -0,0 +1,255 @@
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path;
+use std::str;
+
+use super::datafile::update;
+use super::entry::Entry;
+use super::weight::increase_weight;
+use crate::{Backend, Config, Error, Result};
+
+/// How an entry of another database is combined with ours for the same
+/// path.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MergeStrategy {
+    /// Keep the larger weight. Merging the same file twice changes nothing.
+    Max,
+    /// Add up the weights like `--add` does, as if all the visits happened
+    /// here.
+    Sum,
+    /// Keep whichever entry was visited last, falling back to ours when
+    /// that is unknown.
+    Recent,
+}
+
+impl str::FromStr for MergeStrategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<MergeStrategy, ()> {
+        match s {
+            "max" => Ok(MergeStrategy::Max),
+            "sum" => Ok(MergeStrategy::Sum),
+            "recent" => Ok(MergeStrategy::Recent),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Replaces the leading `from` components of paths by `to`, e.g. to turn
+/// `/home/alice` into `/Users/alice`.
+#[derive(Clone, Debug)]
+pub struct Rewrite {
+    pub from: path::PathBuf,
+    pub to: path::PathBuf,
+}
+
+impl Rewrite {
+    pub fn new<P, Q>(from: P, to: Q) -> Rewrite
+    where
+        P: Into<path::PathBuf>,
+        Q: Into<path::PathBuf>,
+    {
+        Rewrite {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// Returns the rewritten path, or `None` if `p` is not below `from`.
+    pub fn apply(&self, p: &path::Path) -> Option<path::PathBuf> {
+        p.strip_prefix(&self.from).ok().map(|rest| {
+            if rest.as_os_str().is_empty() {
+                self.to.clone()
+            } else {
+                self.to.join(rest)
+            }
+        })
+    }
+}
+
+fn merge_entry(ours: &mut Entry, theirs: &Entry, strategy: MergeStrategy) {
+    let last_access = ours.last_access.max(theirs.last_access);
+
+    match strategy {
+        MergeStrategy::Max => {
+            ours.weight = ours.weight.max(theirs.weight);
+            ours.visits = ours.visits.max(theirs.visits);
+        }
+        MergeStrategy::Sum => {
+            ours.weight = increase_weight(ours.weight, theirs.weight);
+            ours.visits += theirs.visits;
+        }
+        MergeStrategy::Recent => {
+            if theirs.last_access > ours.last_access {
+                ours.weight = theirs.weight;
+                ours.visits = theirs.visits;
+            }
+        }
+    }
+
+    ours.last_access = last_access;
+}
+
+/// Combines `other` into `entries`, applying the first matching rewrite to
+/// every path of `other` first. Returns the number of entries merged.
+pub fn merge(
+    entries: &mut Vec<Entry>,
+    other: &[Entry],
+    strategy: MergeStrategy,
+    rewrites: &[Rewrite],
+) -> usize {
+    for theirs in other {
+        let rewritten = rewrites.iter().find_map(|r| r.apply(&theirs.path));
+        let p = rewritten.as_deref().unwrap_or(&theirs.path);
+
+        match entries.iter_mut().find(|e| e.path == p) {
+            Some(ours) => merge_entry(ours, theirs, strategy),
+            None => {
+                let mut entry = theirs.clone();
+                entry.path = p.to_path_buf();
+                entries.push(entry);
+            }
+        }
+    }
+
+    other.len()
+}
+
+/// Reads a data file of either backend, telling them apart by content.
+fn read_any(p: &path::Path) -> io::Result<Vec<Entry>> {
+    let mut buf = vec![];
+    fs::File::open(p)?.read_to_end(&mut buf)?;
+
+    let backend = if buf.starts_with(b"AJDB") {
+        Backend::Binary
+    } else {
+        Backend::Text
+    };
+    backend.storage().read(&mut buf.as_slice())
+}
+
+/// Merges the data file at `p`, from another machine for example, into
+/// ours. Ignored directories are left out, see `Config::is_ignored`.
+/// Returns the number of entries merged.
+pub fn merge_file<P>(
+    config: &Config,
+    p: P,
+    strategy: MergeStrategy,
+    rewrites: &[Rewrite],
+) -> Result<usize>
+where
+    P: AsRef<path::Path>,
+{
+    let p = p.as_ref();
+    let mut other = read_any(p).map_err(|e| Error::file(p, e))?;
+    other.retain(|e| {
+        let rewritten = rewrites.iter().find_map(|r| r.apply(&e.path));
+        !config.is_ignored(rewritten.as_deref().unwrap_or(&e.path))
+    });
+
+    update(config, |entries| merge(entries, &other, strategy, rewrites))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(p: &str, weight: f64, last_access: Option<u64>, visits: u64) -> Entry {
+        let mut entry = Entry::new(p, weight);
+        entry.last_access = last_access;
+        entry.visits = visits;
+        entry
+    }
+
+    fn merged(strategy: MergeStrategy) -> Vec<Entry> {
+        let mut ours = vec![
+            entry("/foo", 30.0, Some(200), 2),
+            entry("/bar", 1.0, None, 0),
+        ];
+        let theirs = vec![
+            entry("/foo", 40.0, Some(100), 3),
+            entry("/baz", 5.0, Some(300), 1),
+        ];
+        assert_eq!(merge(&mut ours, &theirs, strategy, &[]), 2);
+        ours
+    }
+
+    #[test]
+    fn test_merge_max() {
+        let data = merged(MergeStrategy::Max);
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[0].weight, 40.0);
+        assert_eq!(data[0].last_access, Some(200));
+        assert_eq!(data[0].visits, 3);
+        assert_eq!(data[2].path, path::Path::new("/baz"));
+        assert_eq!(data[2].weight, 5.0);
+    }
+
+    #[test]
+    fn test_merge_sum() {
+        let data = merged(MergeStrategy::Sum);
+        assert_eq!(data[0].weight, 50.0);
+        assert_eq!(data[0].visits, 5);
+    }
+
+    #[test]
+    fn test_merge_recent() {
+        let data = merged(MergeStrategy::Recent);
+        assert_eq!(data[0].weight, 30.0);
+        assert_eq!(data[0].last_access, Some(200));
+
+        let mut ours = vec![entry("/foo", 30.0, None, 0)];
+        let theirs = vec![entry("/foo", 10.0, Some(100), 1)];
+        merge(&mut ours, &theirs, MergeStrategy::Recent, &[]);
+        assert_eq!(ours[0].weight, 10.0);
+        assert_eq!(ours[0].last_access, Some(100));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_merge_rewrite() {
+        let rewrites = [
+            Rewrite::new("/home/alice", "/Users/alice"),
+            Rewrite::new("/home", "/Users/other"),
+        ];
+        let mut ours = vec![entry("/Users/alice/src", 10.0, None, 0)];
+        let theirs = vec![
+            entry("/home/alice/src", 10.0, None, 0),
+            entry("/home/alice", 10.0, None, 0),
+            entry("/home/alicex", 10.0, None, 0),
+            entry("/opt", 10.0, None, 0),
+        ];
+        merge(&mut ours, &theirs, MergeStrategy::Sum, &rewrites);
+
+        let paths: Vec<_> = ours.iter().map(|e| e.path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/Users/alice/src",
+                "/Users/alice",
+                "/Users/other/alicex",
+                "/opt",
+            ]
+        );
+        assert!((ours[0].weight - 200.0f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_merge_file() {
+        let config = Config::from_prefix(&super::super::test_prefix("merge"));
+        let other = config.prefix.join("other.txt");
+        let mut text = "10\t/foo\n20\t/bar\n".to_string();
+        // $HOME is never recorded, not even from elsewhere
+        if let Some(home) = dirs::home_dir() {
+            text.push_str(&format!("30\t{}\n", home.display()));
+        }
+        fs::write(&other, text).unwrap();
+
+        let n = merge_file(&config, &other, MergeStrategy::Max, &[]).unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(super::super::load(&config).unwrap().len(), 2);
+
+        fs::remove_dir_all(&config.prefix).unwrap();
+    }
+}
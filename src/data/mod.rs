@@ -1,5 +1,34 @@
+mod aging;
+mod binary;
 mod datafile;
 mod entry;
+mod escape;
+mod import;
+mod journal;
+mod lock;
+mod merge;
+mod storage;
+mod text;
+mod weight;
 
+pub use self::aging::*;
 pub use self::datafile::*;
 pub use self::entry::*;
+pub use self::escape::{decode_path, encode_path};
+pub use self::import::{read_import, ImportFormat};
+pub use self::journal::{append_journal, compact, try_compact};
+pub use self::lock::*;
+pub use self::merge::{merge, merge_file, MergeStrategy, Rewrite};
+pub use self::storage::Storage;
+pub use self::weight::*;
+
+/// Returns a fresh, empty directory for tests to put data files in.
+#[cfg(test)]
+fn test_prefix(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("autojump-test-{}-{}", std::process::id(), name));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,40 @@
+use std::io;
+
+use super::binary::BinaryStorage;
+use super::entry::Entry;
+use super::text::TextStorage;
+use crate::{Backend, Config, Ranking};
+
+/// An on-disk format for the list of entries.
+///
+/// Implementations only deal with (de)serialization; where the data lives,
+/// atomic replacement, backups and locking are handled by the callers.
+pub trait Storage {
+    fn read(&self, r: &mut dyn io::Read) -> io::Result<Vec<Entry>>;
+    fn write(&self, w: &mut dyn io::Write, data: &[Entry]) -> io::Result<()>;
+}
+
+static TEXT_STORAGE: TextStorage = TextStorage { metadata: false };
+static TEXT_STORAGE_WITH_METADATA: TextStorage = TextStorage { metadata: true };
+static BINARY_STORAGE: BinaryStorage = BinaryStorage;
+
+impl Backend {
+    /// Returns the storage for reading files of this format.
+    pub fn storage(&self) -> &'static dyn Storage {
+        match *self {
+            Backend::Text => &TEXT_STORAGE,
+            Backend::Binary => &BINARY_STORAGE,
+        }
+    }
+}
+
+impl Config {
+    /// Returns the storage for the data file, keeping the visit metadata in
+    /// text files only if the ranking makes use of it.
+    pub fn storage(&self) -> &'static dyn Storage {
+        match (self.backend, self.ranking) {
+            (Backend::Text, Ranking::Frecency) => &TEXT_STORAGE_WITH_METADATA,
+            (backend, _) => backend.storage(),
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::io;
+use std::io::{BufRead, Write};
+use std::path;
+use std::str;
+
+use super::entry::Entry;
+use super::escape::{decode_path, encode_path, path_from_bytes, path_to_bytes};
+use super::storage::Storage;
+
+/// The tab-separated text format of the original autojump.
+pub struct TextStorage {
+    /// Whether to write the visit metadata columns. The original autojump
+    /// drops lines with more than two columns, so they are only written when
+    /// the ranking needs them.
+    pub metadata: bool,
+}
+
+/// The first line of data files holding escaped paths. Files without it,
+/// like those of the original autojump, have their paths taken as is. It
+/// has no tab, so those implementations skip it as a malformed line.
+///
+/// It is only written when a path actually needed escaping, so that the
+/// file stays identical to what the original writes otherwise.
+const ESCAPED_HEADER: &[u8] = b"# autojump-rs: escaped paths";
+
+fn line_path(s: &[u8], escaped: bool) -> path::PathBuf {
+    if escaped {
+        decode_path(s)
+    } else {
+        path_from_bytes(s.to_vec())
+    }
+}
+
+/// Reads a line, without the line ending, into `buf`. Returns `false` at
+/// the end of input.
+pub(super) fn read_line(r: &mut dyn BufRead, buf: &mut Vec<u8>) -> io::Result<bool> {
+    buf.clear();
+    if r.read_until(b'\n', buf)? == 0 {
+        return Ok(false);
+    }
+
+    if buf.ends_with(b"\n") {
+        buf.pop();
+        if buf.ends_with(b"\r") {
+            buf.pop();
+        }
+    }
+    Ok(true)
+}
+
+/// Parses an ASCII number out of a column.
+pub(super) fn parse_column<T>(s: &[u8]) -> Option<T>
+where
+    T: str::FromStr,
+{
+    str::from_utf8(s).ok()?.parse().ok()
+}
+
+/// Parses one line of the data file.
+///
+/// Two layouts are understood:
+///
+/// ```ignore
+/// weight\tpath
+/// weight\tlast_access\tvisits\tpath
+/// ```
+///
+/// The former is what the original implementation writes; the latter is only
+/// written for entries carrying visit metadata, when asked to keep it. Paths are always absolute so
+/// they can never be mistaken for the numeric columns.
+fn load_line(line: &[u8], escaped: bool) -> Option<Entry> {
+    let parts: Vec<_> = line.splitn(2, |&b| b == b'\t').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+
+    let weight = parse_column::<f64>(parts[0]).filter(|w: &f64| w.is_finite())?;
+    let rest = parts[1];
+
+    let meta: Vec<_> = rest.splitn(3, |&b| b == b'\t').collect();
+    if meta.len() == 3 {
+        if let (Some(last_access), Some(visits)) = (parse_column(meta[0]), parse_column(meta[1])) {
+            let mut entry = Entry::new(line_path(meta[2], escaped), weight);
+            entry.last_access = Some(last_access);
+            entry.visits = visits;
+            return Some(entry);
+        }
+    }
+
+    Some(Entry::new(line_path(rest, escaped), weight))
+}
+
+impl Storage for TextStorage {
+    fn read(&self, r: &mut dyn io::Read) -> io::Result<Vec<Entry>> {
+        let mut reader = io::BufReader::new(r);
+        let mut line = vec![];
+        let mut result = vec![];
+        let mut escaped = false;
+
+        let mut first = true;
+        while read_line(&mut reader, &mut line)? {
+            if first && line == ESCAPED_HEADER {
+                escaped = true;
+                continue;
+            }
+            first = false;
+
+            if let Some(entry) = load_line(&line, escaped) {
+                result.push(entry)
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn write(&self, w: &mut dyn io::Write, data: &[Entry]) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(w);
+
+        let paths: Vec<_> = data.iter().map(|e| encode_path(&e.path)).collect();
+        let escaped = data
+            .iter()
+            .zip(paths.iter())
+            .any(|(e, p)| path_to_bytes(&e.path) != p.as_bytes());
+        if escaped {
+            writer.write_all(ESCAPED_HEADER)?;
+            writeln!(&mut writer)?;
+        }
+
+        for (entry, path) in data.iter().zip(paths.iter()) {
+            write!(&mut writer, "{}\t", entry.weight)?;
+            // Keep the original two-column layout for entries without metadata.
+            if let (true, Some(last_access)) = (self.metadata, entry.last_access) {
+                write!(&mut writer, "{}\t{}\t", last_access, entry.visits)?;
+            }
+            writeln!(&mut writer, "{}", path)?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path;
+
+    const TEXT_STORAGE: TextStorage = TextStorage { metadata: false };
+
+    #[test]
+    fn test_load_line() {
+        assert!(load_line(b"", false).is_none());
+        assert!(load_line(b"foo", false).is_none());
+        assert!(load_line(b"x\t/foo", false).is_none());
+        assert!(load_line(b"NaN\t/foo", false).is_none());
+        assert!(load_line(b"inf\t/foo", false).is_none());
+
+        let entry = load_line(b"10.5\t/foo/bar", false).unwrap();
+        assert_eq!(entry.path, path::Path::new("/foo/bar"));
+        assert_eq!(entry.weight, 10.5);
+        assert_eq!(entry.last_access, None);
+        assert_eq!(entry.visits, 0);
+
+        let entry = load_line(b"10.5\t1600000000\t3\t/foo/bar", false).unwrap();
+        assert_eq!(entry.path, path::Path::new("/foo/bar"));
+        assert_eq!(entry.weight, 10.5);
+        assert_eq!(entry.last_access, Some(1600000000));
+        assert_eq!(entry.visits, 3);
+
+        // paths containing tabs are kept intact
+        let entry = load_line(b"1\t/foo\tbar\tbaz", false).unwrap();
+        assert_eq!(entry.path, path::Path::new("/foo\tbar\tbaz"));
+        assert_eq!(entry.last_access, None);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut touched = Entry::new("/bar", 20.0);
+        touched.touch(1600000000);
+        let data = vec![Entry::new("/foo", 10.0), touched];
+
+        // the original two-column layout unless asked for the metadata
+        let mut buf = vec![];
+        TEXT_STORAGE.write(&mut buf, &data).unwrap();
+        assert_eq!(buf, b"10\t/foo\n20\t/bar\n");
+
+        let loaded = TEXT_STORAGE.read(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1].weight, 20.0);
+        assert_eq!(loaded[1].last_access, None);
+
+        let storage = TextStorage { metadata: true };
+        let mut buf = vec![];
+        storage.write(&mut buf, &data).unwrap();
+        assert_eq!(buf, b"10\t/foo\n20\t1600000000\t1\t/bar\n");
+
+        let loaded = storage.read(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1].last_access, Some(1600000000));
+        assert_eq!(loaded[1].visits, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_roundtrip_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let p = path::Path::new(OsStr::from_bytes(b"/foo/\xff\xfe\nbar"));
+        let data = vec![Entry::new(p, 10.0)];
+
+        let mut buf = vec![];
+        TEXT_STORAGE.write(&mut buf, &data).unwrap();
+        assert_eq!(
+            buf,
+            &b"# autojump-rs: escaped paths\n10\t/foo/\\xff\\xfe\\x0abar\n"[..]
+        );
+
+        let loaded = TEXT_STORAGE.read(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded[0].path, p);
+
+        // raw bytes written by other tools are accepted as well
+        let loaded = TEXT_STORAGE.read(&mut &b"10\t/foo/\xff\r\n"[..]).unwrap();
+        assert_eq!(
+            loaded[0].path,
+            path::Path::new(OsStr::from_bytes(b"/foo/\xff"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_legacy_escapes() {
+        // Written as is by the original and older versions, so there is no
+        // escape in there to decode.
+        let loaded = TEXT_STORAGE.read(&mut &b"10\t/srv/a\\x41\n"[..]).unwrap();
+        assert_eq!(loaded[0].path, path::Path::new(r"/srv/a\x41"));
+
+        // and it survives being written back
+        let mut buf = vec![];
+        TEXT_STORAGE.write(&mut buf, &loaded).unwrap();
+        assert_eq!(
+            buf,
+            &b"# autojump-rs: escaped paths\n10\t/srv/a\\x5cx41\n"[..]
+        );
+        let reloaded = TEXT_STORAGE.read(&mut buf.as_slice()).unwrap();
+        assert_eq!(reloaded[0].path, loaded[0].path);
+    }
+}
@@ -0,0 +1,92 @@
+use std::path;
+
+use super::entry::Entry;
+
+pub fn increase_weight(old_w: f64, inc_w: f64) -> f64 {
+    (old_w.powi(2) + inc_w.powi(2)).sqrt()
+}
+
+pub fn decrease_weight(old_w: f64, dec_w: f64) -> f64 {
+    let result = old_w - dec_w;
+
+    if result < 0.0 {
+        0.0
+    } else {
+        result
+    }
+}
+
+/// Increases the weight of `p` by `w`, adding it if not present yet, and
+/// records a visit at `timestamp`. Returns the updated entry.
+pub fn increase<P>(entries: &mut Vec<Entry>, p: P, w: f64, timestamp: u64) -> Entry
+where
+    P: AsRef<path::Path>,
+{
+    let p = p.as_ref();
+    for ent in entries.iter_mut() {
+        if ent.path == p {
+            let new_weight = increase_weight(ent.weight, w);
+            ent.weight = new_weight;
+            ent.touch(timestamp);
+            return ent.clone();
+        }
+    }
+
+    // add the path
+    let mut entry = Entry::new(p, w);
+    entry.touch(timestamp);
+    entries.push(entry.clone());
+    entry
+}
+
+/// Decreases the weight of `p` by `w`, clamping at zero. Returns the updated
+/// entry.
+pub fn decrease<P>(entries: &mut Vec<Entry>, p: P, w: f64) -> Entry
+where
+    P: AsRef<path::Path>,
+{
+    let p = p.as_ref();
+    for ent in entries.iter_mut() {
+        if ent.path == p {
+            let new_weight = decrease_weight(ent.weight, w);
+            ent.weight = new_weight;
+            return ent.clone();
+        }
+    }
+
+    // TODO: original impl also adds an entry in case the requested path is
+    // absent, but is it desirable?
+    // For now let's mimic its behavior...
+    let entry = Entry::new(p, 0.0); // no need to compare
+    entries.push(entry.clone());
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_increase() {
+        let mut entries = vec![Entry::new("/foo", 3.0)];
+
+        let entry = increase(&mut entries, "/foo", 4.0, 100);
+        assert_eq!(entry.weight, 5.0);
+        assert_eq!(entry.last_access, Some(100));
+        assert_eq!(entry.visits, 1);
+
+        let entry = increase(&mut entries, "/bar", 10.0, 200);
+        assert_eq!(entry.weight, 10.0);
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn test_decrease() {
+        let mut entries = vec![Entry::new("/foo", 20.0)];
+
+        assert_eq!(decrease(&mut entries, "/foo", 15.0).weight, 5.0);
+        assert_eq!(decrease(&mut entries, "/foo", 15.0).weight, 0.0);
+        assert_eq!(decrease(&mut entries, "/bar", 15.0).weight, 0.0);
+        assert_eq!(entries.len(), 2);
+    }
+}
@@ -0,0 +1,64 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path;
+use std::result;
+
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error not tied to a particular file.
+    Io(io::Error),
+    /// An I/O error while accessing the given file.
+    File(path::PathBuf, io::Error),
+    /// The config file at the given path is malformed.
+    Config(path::PathBuf, String),
+    /// The `AUTOJUMP_*` environment variable of the given name is malformed.
+    Env(String, String),
+    /// The lock file at the given path could not be taken in time.
+    LockTimeout(path::PathBuf),
+    /// A needle could not be compiled into a pattern.
+    Needle(String, String),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl Error {
+    pub fn file<P>(path: P, err: io::Error) -> Error
+    where
+        P: Into<path::PathBuf>,
+    {
+        Error::File(path.into(), err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::File(p, e) => write!(f, "{}: {}", p.to_string_lossy(), e),
+            Error::Config(p, msg) => {
+                write!(f, "invalid config file {}: {}", p.to_string_lossy(), msg)
+            }
+            Error::Env(key, msg) => write!(f, "invalid environment variable {}: {}", key, msg),
+            Error::LockTimeout(p) => {
+                write!(f, "timed out waiting for lock {}", p.to_string_lossy())
+            }
+            Error::Needle(needle, msg) => write!(f, "invalid needle {}: {}", needle, msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) | Error::File(_, e) => Some(e),
+            Error::Config(..) | Error::Env(..) | Error::LockTimeout(_) | Error::Needle(..) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
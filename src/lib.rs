@@ -2,6 +2,9 @@
 
 pub mod config;
 pub mod data;
+mod error;
 pub mod matcher;
+pub mod project;
 
 pub use self::config::*;
+pub use self::error::{Error, Result};
@@ -0,0 +1,76 @@
+use std::path;
+
+use regex::bytes;
+
+use super::normalize::Normalizer;
+use super::{path_key, re_based};
+
+/// How the pattern of a `Filter` is interpreted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterKind {
+    /// Found anywhere in the path, like the needles of the anywhere stage.
+    Plain,
+    /// A regular expression searched for in the whole path.
+    Regex,
+    /// A shell pattern matched against the whole path, where `*` also
+    /// matches path separators.
+    Glob,
+}
+
+/// A needle that only restricts the paths accepted by the other needles,
+/// instead of being matched itself.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Filter<'a> {
+    pub kind: FilterKind,
+    pub pattern: &'a str,
+    /// Whether to reject the paths that match instead.
+    pub negated: bool,
+}
+
+enum Test {
+    Regex(bytes::Regex),
+    Glob(glob::Pattern),
+}
+
+pub struct CompiledFilter {
+    test: Test,
+    negated: bool,
+}
+
+impl CompiledFilter {
+    /// Plain patterns follow `ignore_case`, while regular expressions and
+    /// shell patterns are taken as written.
+    pub fn new(
+        filter: &Filter,
+        ignore_case: bool,
+        normalizer: &Normalizer,
+    ) -> Result<CompiledFilter, String> {
+        let pattern = normalizer.str(filter.pattern);
+        let test = match filter.kind {
+            FilterKind::Plain => Test::Regex(re_based::prepare_regex(
+                &[&pattern],
+                re_based::re_match_anywhere,
+                ignore_case,
+            )),
+            FilterKind::Regex => {
+                Test::Regex(bytes::Regex::new(&pattern).map_err(|e| e.to_string())?)
+            }
+            FilterKind::Glob => {
+                Test::Glob(glob::Pattern::new(&pattern).map_err(|e| e.to_string())?)
+            }
+        };
+
+        Ok(CompiledFilter {
+            test,
+            negated: filter.negated,
+        })
+    }
+
+    pub fn accepts(&self, p: &path::Path, normalizer: &Normalizer) -> bool {
+        let matched = match &self.test {
+            Test::Regex(re) => re.is_match(&path_key(p, normalizer)),
+            Test::Glob(pattern) => pattern.matches(&normalizer.str(&p.to_string_lossy())),
+        };
+        matched != self.negated
+    }
+}
@@ -1,40 +1,127 @@
+use std::borrow::Cow;
 use std::iter;
 use std::path;
 
-use strsim;
+use super::normalize::Normalizer;
+use super::similarity::Similarity;
+use super::MatcherOptions;
 
-/// A fuzzy matcher based on Jaro-Winkler distances.
+/// A fuzzy matcher based on string similarities, Jaro-Winkler by default.
 ///
-/// The similarity is calculated between the last component of the path and
-/// the last part of the needle.
+/// The last needle is compared against the last component of the path, and
+/// every other needle against one of the parent components, keeping their
+/// order, so that `prj api` finds `~/projects/foo/api-server`. The
+/// similarity of a path is the mean over all needles.
 pub struct FuzzyMatcher<'a> {
-    needle: &'a str,
+    needles: Vec<Cow<'a, str>>,
     threshold: f64,
+    similarity: &'static dyn Similarity,
+    normalizer: Normalizer,
 }
 
-const DEFAULT_FUZZY_THRESHOLD: f64 = 0.6;
+pub const DEFAULT_FUZZY_THRESHOLD: f64 = 0.6;
+
+/// Similarities of the needles against a path.
+struct Scores {
+    last: f64,
+    mean: f64,
+}
+
+/// Returns the greatest sum of similarities of `needles` to distinct
+/// `components`, taken in the same order, or `None` if there are fewer
+/// components than needles.
+fn align(sim: &dyn Similarity, needles: &[Cow<str>], components: &[Cow<str>]) -> Option<f64> {
+    if needles.len() > components.len() {
+        return None;
+    }
+
+    // best[j] is the best sum for the needles so far using only the first
+    // j components.
+    let mut best = vec![0.0f64; components.len() + 1];
+    for (i, needle) in needles.iter().enumerate() {
+        let mut next = vec![f64::NEG_INFINITY; components.len() + 1];
+        for j in (i + 1)..=components.len() {
+            let s = sim.similarity(needle, &components[j - 1]);
+            next[j] = next[j - 1].max(best[j - 1] + s);
+        }
+        best = next;
+    }
+
+    Some(best[components.len()])
+}
 
 impl<'a> FuzzyMatcher<'a> {
-    pub fn defaults(needle: &'a str) -> FuzzyMatcher<'a> {
-        FuzzyMatcher::new(needle, DEFAULT_FUZZY_THRESHOLD)
+    pub fn new(needles: &[&'a str], options: &MatcherOptions) -> FuzzyMatcher<'a> {
+        let normalizer = options.normalizer();
+        FuzzyMatcher {
+            needles: needles.iter().map(|s| normalizer.str(s)).collect(),
+            threshold: options.fuzzy_threshold,
+            similarity: options.fuzzy_algorithm.similarity(),
+            normalizer,
+        }
+    }
+
+    /// Returns the minimum similarity of accepted paths.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    fn scores(&self, p: &path::Path) -> Option<Scores> {
+        let components: Vec<_> = p
+            .components()
+            .filter_map(|c| match c {
+                path::Component::Normal(s) => match s.to_string_lossy() {
+                    Cow::Borrowed(s) => Some(self.normalizer.str(s)),
+                    Cow::Owned(s) => Some(Cow::Owned(self.normalizer.str(&s).into_owned())),
+                },
+                _ => None,
+            })
+            .collect();
+
+        let (last_needle, parent_needles) = self.needles.split_last()?;
+        let (last_component, parents) = components.split_last()?;
+
+        let last = self.similarity.similarity(last_needle, last_component);
+        let parents = align(self.similarity, parent_needles, parents)?;
+        Some(Scores {
+            last,
+            mean: (last + parents) / self.needles.len() as f64,
+        })
     }
 
-    pub fn new(needle: &'a str, threshold: f64) -> FuzzyMatcher<'a> {
-        FuzzyMatcher { needle, threshold }
+    /// Returns the similarity between the needles and the components of
+    /// `p`, or `None` if there are too few components to compare with.
+    pub fn similarity(&self, p: &path::Path) -> Option<f64> {
+        self.scores(p).map(|s| s.mean)
+    }
+
+    /// Returns the similarity of `p` if it is a match: both the last
+    /// needle alone and all of them on average have to be similar enough.
+    pub fn accepts(&self, p: &path::Path) -> Option<f64> {
+        self.scores(p)
+            .filter(|s| s.last >= self.threshold && s.mean >= self.threshold)
+            .map(|s| s.mean)
     }
 
     pub fn filter_path<'p, P>(&'a self, paths: &'p [P]) -> impl iter::Iterator<Item = &'p P> + 'a
+    where
+        P: AsRef<path::Path>,
+        'p: 'a,
+    {
+        self.filter_path_with_similarity(paths).map(|(p, _)| p)
+    }
+
+    /// Like `filter_path`, but also yields the similarity of each path.
+    pub fn filter_path_with_similarity<'p, P>(
+        &'a self,
+        paths: &'p [P],
+    ) -> impl iter::Iterator<Item = (&'p P, f64)> + 'a
     where
         P: AsRef<path::Path>,
         'p: 'a,
     {
         paths
             .iter()
-            .map(|p| (p.as_ref().file_name(), p))
-            .filter(|&(s, _)| s.is_some())
-            .map(|(s, p)| (s.unwrap().to_string_lossy().into_owned(), p))
-            .map(move |(s, p)| (strsim::jaro_winkler(self.needle, &s), p))
-            .filter(move |&(sim, _)| sim >= self.threshold)
-            .map(|(_, p)| p)
+            .filter_map(move |p| self.accepts(p.as_ref()).map(|sim| (p, sim)))
     }
 }
@@ -1,20 +1,155 @@
+mod filter;
 mod fuzzy;
+mod normalize;
+mod pinyin;
 mod re_based;
+mod similarity;
 
 #[cfg(all(test, not(windows)))]
 mod tests;
 #[cfg(all(test, windows))]
 mod tests_windows;
 
+use std::borrow::Cow;
 use std::iter;
 use std::path;
+use std::str;
 
-use regex;
+use regex::bytes;
+use serde_derive::Deserialize;
+
+use crate::{Error, Result};
+
+pub use self::filter::{Filter, FilterKind};
+use self::normalize::Normalizer;
+pub use self::similarity::{
+    Algorithm, DamerauLevenshtein, JaroWinkler, Levenshtein, Similarity, Subsequence,
+};
+
+/// How `Matcher::rank` orders its results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// Each path once, by a score combining match quality and weight.
+    Scored,
+    /// Like the original autojump: everything accepted by each stage in
+    /// turn, so a path may come up more than once.
+    Compat,
+}
+
+impl str::FromStr for MatchMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<MatchMode, ()> {
+        match s {
+            "scored" => Ok(MatchMode::Scored),
+            "compat" => Ok(MatchMode::Compat),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Tunables of the matcher that are independent of the needles.
+#[derive(Clone, Debug)]
+pub struct MatcherOptions {
+    /// Minimum similarity for the fuzzy stage to accept a path.
+    pub fuzzy_threshold: f64,
+    pub fuzzy_algorithm: Algorithm,
+    pub mode: MatchMode,
+    /// Whether accented letters match their unaccented counterparts.
+    pub fold_accents: bool,
+}
+
+impl MatcherOptions {
+    pub fn defaults() -> MatcherOptions {
+        MatcherOptions {
+            fuzzy_threshold: fuzzy::DEFAULT_FUZZY_THRESHOLD,
+            fuzzy_algorithm: Algorithm::JaroWinkler,
+            mode: MatchMode::Compat,
+            fold_accents: false,
+        }
+    }
+
+    fn normalizer(&self) -> Normalizer {
+        Normalizer {
+            fold_accents: self.fold_accents,
+        }
+    }
+}
+
+/// Match quality of a path whose last components match the needles, and of
+/// one whose last component is the last needle, respectively.
+const QUALITY_CONSECUTIVE: f64 = 0.8;
+const QUALITY_EXACT: f64 = 1.0;
+/// Upper bound of the match quality of fuzzy matches, reached for a
+/// similarity of 1.
+const QUALITY_FUZZY: f64 = 0.6;
+const QUALITY_ANYWHERE: f64 = 0.3;
+/// Match quality of pinyin matches relative to the fuzzy threshold, so that
+/// they rank below the weakest fuzzy match, and with the default threshold
+/// above anywhere matches.
+const QUALITY_PINYIN: f64 = 0.55;
+
+/// Combines the quality of a match with the weight of the path.
+///
+/// The weight is damped logarithmically so that a much better match can
+/// make up for a lower weight, while among equally good matches the heavier
+/// path still wins.
+pub fn combined_score(quality: f64, weight: f64) -> f64 {
+    quality * (1.0 + weight.max(0.0).ln_1p())
+}
+
+/// The stage of the matcher that accepted a path.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchKind {
+    /// The needles match the last components of the path, in order.
+    Consecutive,
+    /// The components are similar enough to the needles.
+    Fuzzy,
+    /// The needles appear somewhere in the path, in order.
+    Anywhere,
+    /// The needles match the pinyin of the components, like `cs` does
+    /// `测试`.
+    Pinyin,
+}
+
+impl MatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            MatchKind::Consecutive => "consecutive",
+            MatchKind::Fuzzy => "fuzzy",
+            MatchKind::Anywhere => "anywhere",
+            MatchKind::Pinyin => "pinyin",
+        }
+    }
+}
+
+/// A path accepted by the matcher, and why.
+#[derive(Debug)]
+pub struct Match<'b, P> {
+    pub item: &'b P,
+    pub kind: MatchKind,
+    /// Similarity between the components of the path and the needles as
+    /// computed by the fuzzy stage, whichever stage accepted it.
+    pub similarity: Option<f64>,
+    /// What results are ordered by: the combined score from `rank` in
+    /// scored mode, the weight in compat mode, and the match quality, from
+    /// 0 to 1, otherwise.
+    pub score: f64,
+}
 
 pub struct Matcher<'a> {
+    last_needle: Cow<'a, str>,
+    ignore_case: bool,
+    normalizer: Normalizer,
+    mode: MatchMode,
     fuzzy_matcher: fuzzy::FuzzyMatcher<'a>,
-    re_anywhere: regex::Regex,
-    re_consecutive: regex::Regex,
+    re_anywhere: bytes::Regex,
+    re_consecutive: bytes::Regex,
+    pinyin_matcher: Option<pinyin::PinyinMatcher>,
+    filters: Vec<filter::CompiledFilter>,
+    /// Only paths below this one match, if set.
+    root: Option<path::PathBuf>,
 }
 
 /// Returns whether matches should ignore case based on uppercase letter's
@@ -31,44 +166,266 @@ fn detect_smartcase(needles: &[&str]) -> bool {
     true
 }
 
-fn filter_path_with_re<'a, P>(
-    input: &'a [P],
-    re: &'a regex::Regex,
-) -> impl iter::Iterator<Item = &'a P>
+/// Returns the bytes of `p` for the regexes to match on.
+fn path_key<'p>(p: &'p path::Path, normalizer: &Normalizer) -> Cow<'p, [u8]> {
+    // Match on the raw bytes, so paths that aren't valid Unicode work too.
+    normalizer.bytes(p.as_os_str().as_encoded_bytes())
+}
+
+fn filter_path_with_re<'a, 'p, P>(
+    input: &'p [P],
+    re: &'a bytes::Regex,
+    normalizer: Normalizer,
+) -> impl iter::Iterator<Item = &'p P> + 'a
 where
     P: AsRef<path::Path>,
+    'p: 'a,
 {
     input
         .iter()
-        .filter(move |&p| re.is_match(p.as_ref().to_string_lossy().to_mut()))
+        .filter(move |&p| re.is_match(&path_key(p.as_ref(), &normalizer)))
 }
 
 impl<'a> Matcher<'a> {
     pub fn new_smartcase(needles: Vec<&'a str>) -> Matcher<'a> {
-        let ignore_case = detect_smartcase(&needles);
-        Matcher::new(needles, ignore_case)
+        Matcher::new_smartcase_with_options(needles, &MatcherOptions::defaults())
     }
 
     pub fn new(needles: Vec<&'a str>, ignore_case: bool) -> Matcher<'a> {
-        let fuzzy_matcher = fuzzy::FuzzyMatcher::defaults(needles[needles.len() - 1]);
+        Matcher::with_options(needles, ignore_case, &MatcherOptions::defaults())
+    }
+
+    pub fn new_smartcase_with_options(
+        needles: Vec<&'a str>,
+        options: &MatcherOptions,
+    ) -> Matcher<'a> {
+        let ignore_case = detect_smartcase(&needles);
+        Matcher::with_options(needles, ignore_case, options)
+    }
+
+    pub fn with_options(
+        needles: Vec<&'a str>,
+        ignore_case: bool,
+        options: &MatcherOptions,
+    ) -> Matcher<'a> {
+        let normalizer = options.normalizer();
+        let fuzzy_matcher = fuzzy::FuzzyMatcher::new(&needles, options);
+
+        let normalized: Vec<_> = needles.iter().map(|s| normalizer.str(s)).collect();
+        let normalized: Vec<_> = normalized.iter().map(|s| s.as_ref()).collect();
         let re_anywhere =
-            re_based::prepare_regex(&needles, re_based::re_match_anywhere, ignore_case);
+            re_based::prepare_regex(&normalized, re_based::re_match_anywhere, ignore_case);
         let re_consecutive =
-            re_based::prepare_regex(&needles, re_based::re_match_consecutive, ignore_case);
+            re_based::prepare_regex(&normalized, re_based::re_match_consecutive, ignore_case);
 
         Matcher {
+            last_needle: normalizer.str(needles[needles.len() - 1]),
+            ignore_case,
+            normalizer,
+            mode: options.mode,
             fuzzy_matcher,
             re_anywhere,
             re_consecutive,
+            pinyin_matcher: pinyin::PinyinMatcher::new(&needles),
+            filters: vec![],
+            root: None,
         }
     }
 
+    /// Restricts the matches to the paths accepted by every one of
+    /// `filters` as well.
+    pub fn with_filters(mut self, filters: &[Filter]) -> Result<Matcher<'a>> {
+        for f in filters {
+            let compiled = filter::CompiledFilter::new(f, self.ignore_case, &self.normalizer)
+                .map_err(|msg| Error::Needle(f.pattern.to_string(), msg))?;
+            self.filters.push(compiled);
+        }
+        Ok(self)
+    }
+
+    /// Restricts the matches to `root` and the paths below it.
+    pub fn under<P>(mut self, root: P) -> Matcher<'a>
+    where
+        P: Into<path::PathBuf>,
+    {
+        self.root = Some(root.into());
+        self
+    }
+
+    fn passes_filters(&self, p: &path::Path) -> bool {
+        if let Some(root) = &self.root {
+            if !p.starts_with(root) {
+                return false;
+            }
+        }
+        self.filters.iter().all(|f| f.accepts(p, &self.normalizer))
+    }
+
     pub fn execute<'b, P>(&'b self, haystack: &'b [P]) -> impl iter::Iterator<Item = &'b P>
     where
         P: AsRef<path::Path>,
     {
-        filter_path_with_re(haystack, &self.re_consecutive)
+        filter_path_with_re(haystack, &self.re_consecutive, self.normalizer)
             .chain(self.fuzzy_matcher.filter_path(haystack))
-            .chain(filter_path_with_re(haystack, &self.re_anywhere))
+            .chain(filter_path_with_re(
+                haystack,
+                &self.re_anywhere,
+                self.normalizer,
+            ))
+            .chain(self.filter_path_pinyin(haystack))
+            .filter(move |p| self.passes_filters(p.as_ref()))
+    }
+
+    fn filter_path_pinyin<'b, 'p, P>(
+        &'b self,
+        haystack: &'p [P],
+    ) -> impl iter::Iterator<Item = &'p P> + 'b
+    where
+        P: AsRef<path::Path>,
+        'p: 'b,
+    {
+        self.pinyin_matcher
+            .iter()
+            .flat_map(move |m| m.filter_path(haystack))
+    }
+
+    /// Like `execute`, but tells which stage accepted each path.
+    ///
+    /// The order is the same, so a path accepted by several stages is
+    /// yielded several times too.
+    pub fn execute_detailed<'b, 'p, P>(
+        &'b self,
+        haystack: &'p [P],
+    ) -> impl iter::Iterator<Item = Match<'p, P>> + 'b
+    where
+        P: AsRef<path::Path>,
+        'p: 'b,
+    {
+        let with_kind = move |kind| {
+            move |item: &'p P| Match {
+                item,
+                kind,
+                similarity: self.fuzzy_matcher.similarity(item.as_ref()),
+                score: self.quality(item.as_ref(), kind, None),
+            }
+        };
+
+        filter_path_with_re(haystack, &self.re_consecutive, self.normalizer)
+            .map(with_kind(MatchKind::Consecutive))
+            .chain(
+                self.fuzzy_matcher
+                    .filter_path_with_similarity(haystack)
+                    .map(move |(item, sim)| Match {
+                        item,
+                        kind: MatchKind::Fuzzy,
+                        similarity: Some(sim),
+                        score: self.quality(item.as_ref(), MatchKind::Fuzzy, Some(sim)),
+                    }),
+            )
+            .chain(
+                filter_path_with_re(haystack, &self.re_anywhere, self.normalizer)
+                    .map(with_kind(MatchKind::Anywhere)),
+            )
+            .chain(
+                self.filter_path_pinyin(haystack)
+                    .map(with_kind(MatchKind::Pinyin)),
+            )
+            .filter(move |m| self.passes_filters(m.item.as_ref()))
+    }
+
+    /// Returns whether the last component of `p` is the last needle.
+    fn is_exact(&self, p: &path::Path) -> bool {
+        let name = match p.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return false,
+        };
+        let name = self.normalizer.str(&name);
+
+        if self.ignore_case {
+            name.to_lowercase() == self.last_needle.to_lowercase()
+        } else {
+            name == self.last_needle
+        }
+    }
+
+    fn quality(&self, p: &path::Path, kind: MatchKind, similarity: Option<f64>) -> f64 {
+        match kind {
+            MatchKind::Consecutive if self.is_exact(p) => QUALITY_EXACT,
+            MatchKind::Consecutive => QUALITY_CONSECUTIVE,
+            MatchKind::Fuzzy => QUALITY_FUZZY * similarity.unwrap_or(0.0),
+            MatchKind::Anywhere => QUALITY_ANYWHERE,
+            MatchKind::Pinyin => QUALITY_PINYIN * self.fuzzy_matcher.threshold(),
+        }
+    }
+
+    /// Returns the best match of `p`, if any, scored by quality alone.
+    fn best_match<'p, P>(&self, p: &'p P) -> Option<Match<'p, P>>
+    where
+        P: AsRef<path::Path>,
+    {
+        let path = p.as_ref();
+        if !self.passes_filters(path) {
+            return None;
+        }
+
+        let bytes = path_key(path, &self.normalizer);
+        let similarity = self.fuzzy_matcher.similarity(path);
+
+        let kind = if self.re_consecutive.is_match(&bytes) {
+            MatchKind::Consecutive
+        } else if self.fuzzy_matcher.accepts(path).is_some() {
+            MatchKind::Fuzzy
+        } else if self.re_anywhere.is_match(&bytes) {
+            MatchKind::Anywhere
+        } else if self
+            .pinyin_matcher
+            .as_ref()
+            .is_some_and(|m| m.accepts(path))
+        {
+            MatchKind::Pinyin
+        } else {
+            return None;
+        };
+
+        Some(Match {
+            item: p,
+            kind,
+            similarity,
+            score: self.quality(path, kind, similarity),
+        })
+    }
+
+    /// Returns the paths in `haystack` that match, best first, according to
+    /// the mode the matcher was created with. `weight` gives the weight of
+    /// each path for ranking.
+    ///
+    /// In compat mode this is what `execute` returns, with each stage
+    /// keeping the order of `haystack`.
+    pub fn rank<'p, P, F>(&self, haystack: &'p [P], weight: F) -> Vec<Match<'p, P>>
+    where
+        P: AsRef<path::Path>,
+        F: Fn(&P) -> f64,
+    {
+        if self.mode == MatchMode::Compat {
+            return self
+                .execute_detailed(haystack)
+                .map(|m| Match {
+                    score: weight(m.item),
+                    ..m
+                })
+                .collect();
+        }
+
+        let mut result: Vec<_> = haystack
+            .iter()
+            .filter_map(|p| self.best_match(p))
+            .map(|m| Match {
+                score: combined_score(m.score, weight(m.item)),
+                ..m
+            })
+            .collect();
+        // The sort is stable, so ties keep the order of `haystack`.
+        result.sort_by(|a, b| b.score.total_cmp(&a.score));
+        result
     }
 }
@@ -0,0 +1,78 @@
+//! Canonical forms of needles and paths, so that they compare equal however
+//! the same text happens to be encoded.
+//!
+//! macOS stores file names decomposed (NFD), while most input methods
+//! produce composed characters (NFC), so both sides are brought to NFC.
+//! Optionally accents are dropped too, so that `resume` finds `résumé`.
+
+use std::borrow::Cow;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Normalizer {
+    pub fold_accents: bool,
+}
+
+impl Normalizer {
+    pub fn str<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        // Nothing to do for the vast majority of paths.
+        if s.is_ascii() {
+            return Cow::Borrowed(s);
+        }
+
+        if self.fold_accents {
+            Cow::Owned(s.nfd().filter(|&c| !is_combining_mark(c)).nfc().collect())
+        } else {
+            Cow::Owned(s.nfc().collect())
+        }
+    }
+
+    /// Normalizes the parts of `b` that are valid UTF-8, leaving any other
+    /// bytes alone.
+    pub fn bytes<'s>(&self, b: &'s [u8]) -> Cow<'s, [u8]> {
+        if b.is_ascii() {
+            return Cow::Borrowed(b);
+        }
+
+        let mut result = Vec::with_capacity(b.len());
+        for chunk in b.utf8_chunks() {
+            result.extend_from_slice(self.str(chunk.valid()).as_bytes());
+            result.extend_from_slice(chunk.invalid());
+        }
+        Cow::Owned(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NFC: &str = "caf\u{e9}";
+    const NFD: &str = "cafe\u{301}";
+
+    #[test]
+    fn test_normalize() {
+        let n = Normalizer {
+            fold_accents: false,
+        };
+        assert_eq!(n.str("cafe"), "cafe");
+        assert_eq!(n.str(NFC), NFC);
+        assert_eq!(n.str(NFD), NFC);
+        assert_eq!(
+            n.bytes(b"/foo\xff/cafe\xcc\x81"),
+            &b"/foo\xff/caf\xc3\xa9"[..]
+        );
+    }
+
+    #[test]
+    fn test_fold_accents() {
+        let n = Normalizer { fold_accents: true };
+        assert_eq!(n.str(NFC), "cafe");
+        assert_eq!(n.str(NFD), "cafe");
+        assert_eq!(n.str("R\u{e9}sum\u{e9}"), "Resume");
+        // not accents
+        assert_eq!(n.str("\u{6d4b}\u{8bd5}"), "\u{6d4b}\u{8bd5}");
+    }
+}
@@ -0,0 +1,167 @@
+//! Matching of Latin needles against the pinyin of CJK path components, so
+//! that `cs` or `ceshi` find `测试`.
+//!
+//! The transliteration tables are only built in with the `pinyin` feature;
+//! without it there is no `PinyinMatcher` to be had.
+
+use std::path;
+
+/// Returns whether `c` is a CJK ideograph.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{2fa1f}')
+}
+
+#[cfg(feature = "pinyin")]
+fn syllable(c: char) -> Option<&'static str> {
+    deunicode::deunicode_char(c).map(|s| s.trim())
+}
+
+#[cfg(not(feature = "pinyin"))]
+fn syllable(_: char) -> Option<&'static str> {
+    None
+}
+
+/// A path component as spelled in pinyin.
+struct Spelling {
+    /// All syllables in full, e.g. `ceshi` for `测试`.
+    full: String,
+    /// The first letter of each syllable, e.g. `cs` for `测试`.
+    initials: String,
+    /// Whether any ideographs were spelled out at all.
+    transliterated: bool,
+}
+
+impl Spelling {
+    fn new(component: &str) -> Spelling {
+        let mut result = Spelling {
+            full: String::new(),
+            initials: String::new(),
+            transliterated: false,
+        };
+
+        for c in component.chars() {
+            match syllable(c).filter(|s| is_cjk(c) && !s.is_empty()) {
+                Some(s) => {
+                    let s = s.to_lowercase();
+                    result.full.push_str(&s);
+                    result.initials.extend(s.chars().next());
+                    result.transliterated = true;
+                }
+                None => {
+                    // Anything else is kept, so that mixed names like
+                    // `项目docs` work too.
+                    result.full.extend(c.to_lowercase());
+                    result.initials.extend(c.to_lowercase());
+                }
+            }
+        }
+
+        result
+    }
+
+    fn contains(&self, needle: &str) -> bool {
+        self.full.contains(needle) || self.initials.contains(needle)
+    }
+}
+
+pub struct PinyinMatcher {
+    needles: Vec<String>,
+}
+
+impl PinyinMatcher {
+    /// Returns `None` unless the feature is enabled and all needles are
+    /// ASCII, since only Latin needles can be pinyin.
+    pub fn new(needles: &[&str]) -> Option<PinyinMatcher> {
+        if !cfg!(feature = "pinyin") || !needles.iter().all(|s| s.is_ascii()) {
+            return None;
+        }
+
+        Some(PinyinMatcher {
+            needles: needles.iter().map(|s| s.to_ascii_lowercase()).collect(),
+        })
+    }
+
+    /// Returns whether the last needle is found in the last component of
+    /// `p`, and the other needles in earlier components in order, at least
+    /// one of them by its pinyin.
+    pub fn accepts(&self, p: &path::Path) -> bool {
+        let spellings: Vec<_> = p
+            .components()
+            .filter_map(|c| match c {
+                path::Component::Normal(s) => Some(Spelling::new(&s.to_string_lossy())),
+                _ => None,
+            })
+            .collect();
+
+        let (last_needle, needles) = match self.needles.split_last() {
+            Some(x) => x,
+            None => return false,
+        };
+        let (last, mut rest) = match spellings.split_last() {
+            Some(x) if x.0.contains(last_needle) => x,
+            _ => return false,
+        };
+
+        // The rightmost match of each needle leaves the most room for the
+        // ones before it.
+        let mut transliterated = last.transliterated;
+        for needle in needles.iter().rev() {
+            match rest.iter().rposition(|s| s.contains(needle)) {
+                Some(i) => {
+                    transliterated |= rest[i].transliterated;
+                    rest = &rest[..i];
+                }
+                None => return false,
+            }
+        }
+
+        transliterated
+    }
+
+    pub fn filter_path<'a, 'p, P>(&'a self, paths: &'p [P]) -> impl Iterator<Item = &'p P> + 'a
+    where
+        P: AsRef<path::Path>,
+        'p: 'a,
+    {
+        paths.iter().filter(move |&p| self.accepts(p.as_ref()))
+    }
+}
+
+#[cfg(all(test, feature = "pinyin"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spelling() {
+        let s = Spelling::new("测试");
+        assert_eq!(s.full, "ceshi");
+        assert_eq!(s.initials, "cs");
+        assert!(s.transliterated);
+
+        let s = Spelling::new("项目Docs");
+        assert_eq!(s.full, "xiangmudocs");
+        assert_eq!(s.initials, "xmdocs");
+
+        // accented letters aren't pinyin
+        assert!(!Spelling::new("Résumé").transliterated);
+    }
+
+    #[test]
+    fn test_accepts() {
+        let matcher = PinyinMatcher::new(&["cs"]).unwrap();
+        assert!(matcher.accepts(path::Path::new("/home/测试")));
+        assert!(!matcher.accepts(path::Path::new("/home/测试/foo")));
+        // plain matches are left to the other stages
+        assert!(!matcher.accepts(path::Path::new("/home/cs")));
+
+        let matcher = PinyinMatcher::new(&["xiangmu", "Docs"]).unwrap();
+        assert!(matcher.accepts(path::Path::new("/home/项目/docs")));
+        assert!(!matcher.accepts(path::Path::new("/home/docs/项目")));
+
+        assert!(PinyinMatcher::new(&["测试"]).is_none());
+    }
+}
@@ -1,8 +1,11 @@
 use std::path;
 
-use regex;
+use regex::bytes;
 
-pub fn prepare_regex<F>(needles: &[&str], f: F, ignore_case: bool) -> regex::Regex
+/// Matches anything, including bytes that aren't valid UTF-8 in paths.
+const ANY: &str = r"(?s-u:.)*";
+
+pub fn prepare_regex<F>(needles: &[&str], f: F, ignore_case: bool) -> bytes::Regex
 where
     F: Fn(&[&str]) -> String,
 {
@@ -12,7 +15,7 @@ where
         tmp.push_str(&f(needles));
         tmp
     };
-    regex::Regex::new(&re).unwrap()
+    bytes::Regex::new(&re).unwrap()
 }
 
 /// Port of Python's `re.escape()`, except that '/' is passed as-is.
@@ -36,10 +39,10 @@ fn re_escape(s: &str) -> String {
 
 pub fn re_match_anywhere(needles: &[&str]) -> String {
     let mut result = String::new();
-    result.push_str(r".*");
+    result.push_str(ANY);
     for s in needles {
         result.push_str(&re_escape(s));
-        result.push_str(r".*");
+        result.push_str(ANY);
     }
     result
 }
@@ -50,7 +53,7 @@ pub fn re_match_consecutive(needles: &[&str]) -> String {
         tmp.push(path::MAIN_SEPARATOR);
         re_escape(&tmp)
     };
-    let no_sep = format!(r"[^{}]*", sep);
+    let no_sep = format!(r"(?-u:[^{}])*", sep);
 
     let mut result = String::new();
     for (i, s) in needles.iter().enumerate() {
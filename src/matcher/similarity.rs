@@ -0,0 +1,172 @@
+use std::str;
+
+use serde_derive::Deserialize;
+use strsim;
+
+/// A measure of how similar a needle is to a path component.
+pub trait Similarity {
+    /// Returns the similarity of `a` and `b`, from 0 for nothing in common
+    /// to 1 for identical strings.
+    fn similarity(&self, a: &str, b: &str) -> f64;
+}
+
+/// Favors strings sharing a prefix; what the original fuzzy stage uses.
+pub struct JaroWinkler;
+
+/// One minus the edit distance relative to the longer string.
+pub struct Levenshtein;
+
+/// Like `Levenshtein`, but swapping two adjacent characters counts as a
+/// single edit.
+pub struct DamerauLevenshtein;
+
+/// fzf-style: the characters of the needle have to appear in order in the
+/// other string, and the closer together they are, the better.
+pub struct Subsequence;
+
+impl Similarity for JaroWinkler {
+    fn similarity(&self, a: &str, b: &str) -> f64 {
+        strsim::jaro_winkler(a, b)
+    }
+}
+
+impl Similarity for Levenshtein {
+    fn similarity(&self, a: &str, b: &str) -> f64 {
+        strsim::normalized_levenshtein(a, b)
+    }
+}
+
+impl Similarity for DamerauLevenshtein {
+    fn similarity(&self, a: &str, b: &str) -> f64 {
+        strsim::normalized_damerau_levenshtein(a, b)
+    }
+}
+
+/// Returns the length of the shortest span of `haystack` containing
+/// `needle` as a subsequence, if any.
+fn shortest_span(needle: &[char], haystack: &[char]) -> Option<usize> {
+    let mut best: Option<usize> = None;
+
+    for start in 0..haystack.len() {
+        if haystack[start] != needle[0] {
+            continue;
+        }
+
+        // Matching greedily from here gives the shortest span starting here.
+        let mut rest = needle[1..].iter().peekable();
+        let mut end = start;
+        for (i, ch) in haystack.iter().enumerate().skip(start + 1) {
+            if rest.peek().is_none() {
+                break;
+            }
+            if Some(&ch) == rest.peek() {
+                rest.next();
+                end = i;
+            }
+        }
+        if rest.peek().is_some() {
+            // no later start can match either
+            break;
+        }
+
+        let span = end - start + 1;
+        best = Some(best.map_or(span, |b| b.min(span)));
+    }
+
+    best
+}
+
+impl Similarity for Subsequence {
+    /// Weighs how tightly packed the matched characters are twice as much as
+    /// how much of `b` they cover.
+    fn similarity(&self, a: &str, b: &str) -> f64 {
+        let needle: Vec<_> = a.chars().collect();
+        let haystack: Vec<_> = b.chars().collect();
+        if needle.is_empty() {
+            return if haystack.is_empty() { 1.0 } else { 0.0 };
+        }
+
+        match shortest_span(&needle, &haystack) {
+            Some(span) => {
+                let n = needle.len() as f64;
+                let tightness = n / span as f64;
+                let coverage = n / haystack.len() as f64;
+                (2.0 * tightness + coverage) / 3.0
+            }
+            None => 0.0,
+        }
+    }
+}
+
+/// The similarity measure used by the fuzzy stage.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Algorithm {
+    JaroWinkler,
+    Levenshtein,
+    DamerauLevenshtein,
+    Subsequence,
+}
+
+impl str::FromStr for Algorithm {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Algorithm, ()> {
+        match s {
+            "jaro-winkler" => Ok(Algorithm::JaroWinkler),
+            "levenshtein" => Ok(Algorithm::Levenshtein),
+            "damerau-levenshtein" => Ok(Algorithm::DamerauLevenshtein),
+            "subsequence" => Ok(Algorithm::Subsequence),
+            _ => Err(()),
+        }
+    }
+}
+
+static JARO_WINKLER: JaroWinkler = JaroWinkler;
+static LEVENSHTEIN: Levenshtein = Levenshtein;
+static DAMERAU_LEVENSHTEIN: DamerauLevenshtein = DamerauLevenshtein;
+static SUBSEQUENCE: Subsequence = Subsequence;
+
+impl Algorithm {
+    pub fn similarity(&self) -> &'static dyn Similarity {
+        match *self {
+            Algorithm::JaroWinkler => &JARO_WINKLER,
+            Algorithm::Levenshtein => &LEVENSHTEIN,
+            Algorithm::DamerauLevenshtein => &DAMERAU_LEVENSHTEIN,
+            Algorithm::Subsequence => &SUBSEQUENCE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distances() {
+        assert_eq!(Levenshtein.similarity("foo", "foo"), 1.0);
+        assert_eq!(Levenshtein.similarity("abcd", "abdc"), 0.5);
+        assert_eq!(DamerauLevenshtein.similarity("abcd", "abdc"), 0.75);
+        assert_eq!(DamerauLevenshtein.similarity("abc", "xyz"), 0.0);
+    }
+
+    #[test]
+    fn test_subsequence() {
+        assert_eq!(Subsequence.similarity("api", "api"), 1.0);
+        assert_eq!(Subsequence.similarity("", ""), 1.0);
+        assert_eq!(Subsequence.similarity("", "api"), 0.0);
+        assert_eq!(Subsequence.similarity("pia", "api"), 0.0);
+        assert_eq!(Subsequence.similarity("api", "ap"), 0.0);
+
+        // "api" at the start of "api-server": tight, a third of it covered
+        assert!((Subsequence.similarity("api", "api-server") - 0.7666).abs() < 1e-3);
+        // the tightest occurrence counts
+        assert_eq!(
+            Subsequence.similarity("ab", "a-b-ab"),
+            Subsequence.similarity("ab", "abxxxx")
+        );
+        assert!(
+            Subsequence.similarity("prj", "projects") > Subsequence.similarity("prj", "p-r-o-j")
+        );
+    }
+}
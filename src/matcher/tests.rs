@@ -6,11 +6,15 @@ use super::fuzzy::*;
 use super::re_based::*;
 use super::*;
 
+fn scored() -> MatcherOptions {
+    MatcherOptions { mode: MatchMode::Scored, ..MatcherOptions::defaults() }
+}
+
 #[test]
 fn test_smartcase() {
     macro_rules! a {
         ($needles: tt, $y: expr) => {
-            assert_eq!(detect_smartcase(&vec! $needles), $y);
+            assert_eq!(detect_smartcase(&$needles), $y);
         };
     }
 
@@ -24,7 +28,7 @@ fn test_smartcase() {
 
 macro_rules! assert_re {
     ($fn_name: ident, $x: tt, $y: expr) => {
-        assert_eq!($fn_name(&vec! $x), $y);
+        assert_eq!($fn_name(&$x), $y);
     };
 }
 
@@ -36,9 +40,9 @@ fn test_re_match_anywhere() {
         };
     }
 
-    a!(["foo"], r".*foo.*");
-    a!(["foo", "baz"], r".*foo.*baz.*");
-    a!(["测试", "baz"], r".*\x{6d4b}\x{8bd5}.*baz.*");
+    a!(["foo"], r"(?s-u:.)*foo(?s-u:.)*");
+    a!(["foo", "baz"], r"(?s-u:.)*foo(?s-u:.)*baz(?s-u:.)*");
+    a!(["测试", "baz"], r"(?s-u:.)*\x{6d4b}\x{8bd5}(?s-u:.)*baz(?s-u:.)*");
 }
 
 #[test]
@@ -49,21 +53,21 @@ fn test_re_match_consecutive() {
         };
     }
 
-    a!(["foo"], r"foo[^/]*$");
-    a!(["foo", "baz"], r"foo[^/]*/[^/]*baz[^/]*$");
-    a!(["测试", "baz"], r"\x{6d4b}\x{8bd5}[^/]*/[^/]*baz[^/]*$");
+    a!(["foo"], r"foo(?-u:[^/])*$");
+    a!(["foo", "baz"], r"foo(?-u:[^/])*/(?-u:[^/])*baz(?-u:[^/])*$");
+    a!(["测试", "baz"], r"\x{6d4b}\x{8bd5}(?-u:[^/])*/(?-u:[^/])*baz(?-u:[^/])*$");
 }
 
 #[test]
 fn test_fuzzy() {
     macro_rules! a {
         ($needle: expr, [$($x: expr, )*], [$($y: expr, )*]) => {
-            let matcher = FuzzyMatcher::defaults($needle);
+            let matcher = FuzzyMatcher::new(&[$needle], &MatcherOptions::defaults());
             let haystack: Vec<&path::Path> = vec![$(path::Path::new($x), )*];
             let expected: Vec<&path::Path> = vec![$(path::Path::new($y), )*];
             let actual: Vec<_> = matcher.filter_path(&haystack).collect();
             assert_eq!(expected.len(), actual.len());
-            for (i, j) in expected.into_iter().zip(actual.into_iter()) {
+            for (i, j) in expected.into_iter().zip(actual) {
                 assert_eq!(&i, j);
             }
         };
@@ -84,6 +88,23 @@ fn test_fuzzy() {
     );
 }
 
+#[test]
+fn test_fuzzy_multiple_needles() {
+    let matcher = FuzzyMatcher::new(&["prj", "api"], &MatcherOptions::defaults());
+    let haystack = [
+        path::Path::new("/home/user/projects/foo/api-server"),
+        path::Path::new("/home/user/music/api-server"),
+        path::Path::new("/home/user/projects/web"),
+        path::Path::new("/api"),
+    ];
+
+    let actual: Vec<_> = matcher.filter_path(&haystack).collect();
+    assert_eq!(actual, [&haystack[0]]);
+
+    // too few components for the needles
+    assert_eq!(matcher.similarity(haystack[3]), None);
+}
+
 #[test]
 fn test_matcher() {
     let needles = vec!["foo", "baz"];
@@ -113,7 +134,209 @@ fn test_matcher() {
         path::Path::new("/foo/baz"),
     ];
     assert_eq!(actual.len(), expected.len());
-    for (i, j) in expected.into_iter().zip(actual.into_iter()) {
+    for (i, j) in expected.into_iter().zip(actual) {
         assert_eq!(&i, j);
     }
 }
+
+#[test]
+fn test_matcher_detailed() {
+    let needles = vec!["foo", "baz"];
+    let matcher = Matcher::new(needles, false);
+
+    let haystack = vec![
+        path::Path::new("/moo/foo/baz"),
+        path::Path::new("/baz/foo/bar"),
+    ];
+
+    let actual: Vec<_> = matcher
+        .execute_detailed(&haystack)
+        .map(|m| (*m.item, m.kind, m.similarity.unwrap()))
+        .collect();
+    let expected = [
+        (haystack[0], MatchKind::Consecutive, 1.0),
+        (haystack[0], MatchKind::Fuzzy, 1.0),
+        (haystack[1], MatchKind::Fuzzy, 0.9111111111111111),
+        (haystack[0], MatchKind::Anywhere, 1.0),
+    ];
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_matcher_normalize() {
+    let kinds = |needle, options: &MatcherOptions, p| -> Vec<_> {
+        let haystack = [path::Path::new(p)];
+        let matcher = Matcher::with_options(vec![needle], true, options);
+        matcher.execute_detailed(&haystack).map(|m| m.kind).collect()
+    };
+    let all = [MatchKind::Consecutive, MatchKind::Fuzzy, MatchKind::Anywhere];
+    let mut options = MatcherOptions::defaults();
+
+    // "café" with a decomposed "é", as macOS stores it
+    assert_eq!(kinds("café", &options, "/docs/cafe\u{301}"), all);
+
+    // without folding, only the fuzzy matcher sees the similarity
+    assert_eq!(kinds("resume", &options, "/docs/Résumé"), [MatchKind::Fuzzy]);
+    options.fold_accents = true;
+    assert_eq!(kinds("resume", &options, "/docs/Résumé"), all);
+}
+
+#[cfg(feature = "pinyin")]
+#[test]
+fn test_matcher_pinyin() {
+    let matcher = Matcher::new_smartcase_with_options(vec!["cs"], &scored());
+    let haystack = vec![
+        path::Path::new("/home/cs"),
+        path::Path::new("/home/测试"),
+        path::Path::new("/home/测试/foo"),
+    ];
+
+    let actual: Vec<_> = matcher
+        .execute_detailed(&haystack)
+        .filter(|m| m.kind == MatchKind::Pinyin)
+        .map(|m| *m.item)
+        .collect();
+    assert_eq!(actual, [haystack[1]]);
+
+    let actual: Vec<_> = matcher.rank(&haystack, |_| 1.0).into_iter().map(|m| *m.item).collect();
+    assert_eq!(actual, [haystack[0], haystack[1]]);
+}
+
+#[cfg(feature = "pinyin")]
+#[test]
+fn test_matcher_pinyin_rank() {
+    let haystack = vec![
+        path::Path::new("/home/测试"),
+        // a fuzzy match of similarity 0.61, weaker than usual
+        path::Path::new("/home/cxxxs"),
+        path::Path::new("/cs/zzz"),
+    ];
+    let rank = |threshold| -> Vec<_> {
+        let options = MatcherOptions {
+            fuzzy_threshold: threshold,
+            ..scored()
+        };
+        let matcher = Matcher::with_options(vec!["cs"], false, &options);
+        matcher.rank(&haystack, |_| 1.0).into_iter().map(|m| m.kind).collect()
+    };
+
+    // pinyin matches rank below even the weakest fuzzy match let through,
+    // and above anywhere matches with the default threshold
+    let expected = [MatchKind::Fuzzy, MatchKind::Pinyin, MatchKind::Anywhere];
+    assert_eq!(rank(0.6), expected);
+    assert_eq!(rank(0.61), expected);
+    assert_eq!(
+        rank(0.5),
+        [MatchKind::Fuzzy, MatchKind::Anywhere, MatchKind::Pinyin]
+    );
+}
+
+#[test]
+fn test_matcher_filters() {
+    let filter = |kind, pattern, negated| Filter { kind, pattern, negated };
+    let haystack = vec![
+        path::Path::new("/src/foo-api"),
+        path::Path::new("/src/target/foo-api"),
+        path::Path::new("/src/foo-web"),
+        path::Path::new("/home/foo-api"),
+    ];
+    let run = |filters: &[Filter]| -> Vec<_> {
+        let matcher = Matcher::with_options(vec!["foo"], true, &scored()).with_filters(filters).unwrap();
+        matcher.rank(&haystack, |_| 1.0).into_iter().map(|m| *m.item).collect()
+    };
+
+    assert_eq!(run(&[filter(FilterKind::Regex, "-api$", false)]), [haystack[0], haystack[1], haystack[3]]);
+    assert_eq!(
+        run(&[
+            filter(FilterKind::Glob, "*/src/*", false),
+            filter(FilterKind::Plain, "TARGET", true),
+        ]),
+        [haystack[0], haystack[2]]
+    );
+    assert_eq!(run(&[filter(FilterKind::Regex, "^/home", true)]).len(), 3);
+
+    let matcher = Matcher::new(vec!["foo"], true).with_filters(&[filter(FilterKind::Regex, "(", false)]);
+    assert!(matcher.is_err());
+}
+
+#[test]
+fn test_matcher_under() {
+    let haystack = vec![
+        path::Path::new("/work/foo"),
+        path::Path::new("/work/foo/src"),
+        path::Path::new("/work/foobar/src"),
+        path::Path::new("/home/src"),
+    ];
+
+    let matcher = Matcher::new(vec!["src"], true).under("/work/foo");
+    let actual: Vec<_> = matcher.execute(&haystack).collect();
+    assert_eq!(actual, [&haystack[1]; 3]);
+
+    let matcher = Matcher::with_options(vec![""], true, &scored()).under("/work/foo");
+    let actual: Vec<_> = matcher.rank(&haystack, |_| 1.0).into_iter().map(|m| *m.item).collect();
+    assert_eq!(actual, [haystack[0], haystack[1]]);
+}
+
+#[test]
+fn test_matcher_rank() {
+    let needles = vec!["foo", "baz"];
+    let matcher = Matcher::with_options(needles.clone(), false, &scored());
+
+    // weights of the paths, in descending order as the data is sorted
+    let haystack = [
+        (path::Path::new("/foo/bar/baz"), 100.0),
+        (path::Path::new("/baz/foo/bar"), 50.0),
+        (path::Path::new("/moo/foo/baz"), 10.0),
+        (path::Path::new("/foo/baz"), 1.0),
+    ];
+    let paths: Vec<_> = haystack.iter().map(|&(p, _)| p).collect();
+    let weight = |p: &&path::Path| haystack.iter().find(|(q, _)| q == p).unwrap().1;
+
+    let actual: Vec<_> = matcher
+        .rank(&paths, weight)
+        .into_iter()
+        .map(|m| (*m.item, m.kind))
+        .collect();
+    let expected = [
+        // an exact match of the last component makes up for a lower weight
+        (paths[2], MatchKind::Consecutive),
+        (paths[0], MatchKind::Fuzzy),
+        (paths[1], MatchKind::Fuzzy),
+        (paths[3], MatchKind::Consecutive),
+    ];
+    assert_eq!(actual, expected);
+
+    // the default is the original order
+    let matcher = Matcher::new(needles, false);
+    let actual: Vec<_> = matcher.rank(&paths, weight).into_iter().map(|m| *m.item).collect();
+    let expected: Vec<_> = matcher.execute(&paths).cloned().collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_matcher_non_utf8() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let p = |b| path::Path::new(OsStr::from_bytes(b));
+    let matcher = Matcher::new(vec!["foo", "baz"], false);
+
+    let haystack = vec![
+        p(b"/foo\xff/baz\xfe"),
+        p(b"/foo/\xffbar/baz"),
+        p(b"/bar/\xff"),
+    ];
+
+    let actual: Vec<_> = matcher.execute(&haystack).collect();
+    let expected = [
+        // consecutive matcher
+        p(b"/foo\xff/baz\xfe"),
+        // fuzzy matcher
+        p(b"/foo\xff/baz\xfe"),
+        p(b"/foo/\xffbar/baz"),
+        // anywhere matcher
+        p(b"/foo\xff/baz\xfe"),
+        p(b"/foo/\xffbar/baz"),
+    ];
+    assert_eq!(actual, expected.iter().collect::<Vec<_>>());
+}
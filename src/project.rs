@@ -0,0 +1,139 @@
+//! Detection of project roots, i.e. directories holding a marker like `.git`
+//! or `Cargo.toml`.
+
+use std::path;
+
+/// Markers used unless configured otherwise.
+pub const DEFAULT_MARKERS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
+
+/// Markers of version control checkouts, as opposed to build files.
+pub const VCS_MARKERS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Returns whether `p` contains any of `markers`.
+pub fn is_root<S>(p: &path::Path, markers: &[S]) -> bool
+where
+    S: AsRef<str>,
+{
+    markers.iter().any(|m| p.join(m.as_ref()).exists())
+}
+
+/// Returns the root of the project containing `p`, `p` itself included.
+///
+/// That is the innermost checkout if any of `markers` is a version control
+/// one, so that the packages of a monorepo, each with its own build file,
+/// all count as part of the repository, while nested clones, submodules and
+/// repositories under a dotfiles checkout of `$HOME` are projects of their
+/// own. Old Subversion checkouts have a `.svn` in every directory and are
+/// followed up to their top. Outside of checkouts it is the innermost
+/// directory with any of the markers.
+pub fn find_root<'p, S>(p: &'p path::Path, markers: &[S]) -> Option<&'p path::Path>
+where
+    S: AsRef<str>,
+{
+    let vcs_markers: Vec<_> = markers
+        .iter()
+        .map(|m| m.as_ref())
+        .filter(|m| VCS_MARKERS.contains(m))
+        .collect();
+
+    let checkout = p
+        .ancestors()
+        .find(|dir| is_root(dir, &vcs_markers))
+        .map(|root| {
+            if vcs_markers.contains(&".svn") {
+                root.ancestors()
+                    .take_while(|dir| dir.join(".svn").exists())
+                    .last()
+                    .unwrap_or(root)
+            } else {
+                root
+            }
+        });
+    checkout.or_else(|| p.ancestors().find(|dir| is_root(dir, markers)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn test_find_root() {
+        let tmp = env::temp_dir().join(format!("autojump-test-{}-project", process::id()));
+        let repo = tmp.join("repo");
+        let inner = repo.join("src").join("bin");
+        fs::create_dir_all(&inner).unwrap();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+
+        assert!(is_root(&repo, DEFAULT_MARKERS));
+        assert!(!is_root(&inner, DEFAULT_MARKERS));
+        assert_eq!(find_root(&inner, DEFAULT_MARKERS), Some(repo.as_path()));
+        assert_eq!(find_root(&inner, &["no-such-marker"]), None);
+
+        // a package of a monorepo is still part of the repository
+        let package = repo.join("packages").join("api");
+        let package_src = package.join("src");
+        fs::create_dir_all(&package_src).unwrap();
+        fs::write(package.join("package.json"), "{}").unwrap();
+        fs::write(repo.join("package.json"), "{}").unwrap();
+        assert_eq!(
+            find_root(&package_src, DEFAULT_MARKERS),
+            Some(repo.as_path())
+        );
+        // unless checkouts don't count
+        assert_eq!(
+            find_root(&package_src, &["package.json"]),
+            Some(package.as_path())
+        );
+
+        // the innermost of nested checkouts, like a submodule
+        fs::create_dir_all(package.join(".git")).unwrap();
+        assert_eq!(
+            find_root(&package_src, DEFAULT_MARKERS),
+            Some(package.as_path())
+        );
+
+        // a repository under a home directory kept in a dotfiles repository
+        let home = tmp.join("home");
+        let dotfiles_repo = home.join("code").join("app");
+        fs::create_dir_all(home.join(".git")).unwrap();
+        fs::create_dir_all(dotfiles_repo.join(".git")).unwrap();
+        fs::create_dir_all(dotfiles_repo.join("src")).unwrap();
+        assert_eq!(
+            find_root(&dotfiles_repo.join("src"), DEFAULT_MARKERS),
+            Some(dotfiles_repo.as_path())
+        );
+        assert_eq!(
+            find_root(&home.join("code"), DEFAULT_MARKERS),
+            Some(home.as_path())
+        );
+
+        // Subversion checkouts with a `.svn` in every directory
+        let svn = tmp.join("svn");
+        let svn_inner = svn.join("trunk").join("src");
+        fs::create_dir_all(&svn_inner).unwrap();
+        for dir in svn_inner.ancestors().take(3) {
+            fs::create_dir_all(dir.join(".svn")).unwrap();
+        }
+        assert_eq!(find_root(&svn_inner, DEFAULT_MARKERS), Some(svn.as_path()));
+
+        // build files outside of checkouts
+        let loose = tmp.join("loose").join("crate");
+        fs::create_dir_all(&loose).unwrap();
+        fs::write(tmp.join("loose").join("Cargo.toml"), "").unwrap();
+        fs::write(loose.join("Cargo.toml"), "").unwrap();
+        assert_eq!(find_root(&loose, DEFAULT_MARKERS), Some(loose.as_path()));
+
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+}